@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::storage::temp_path_for;
+use crate::task::Task;
+
+/// Finished tasks moved out of a list, kept in a sidecar file next to it
+/// (`tasks.json` -> `tasks.archive.json`) until they are purged.
+#[derive(Debug)]
+pub struct Archive {
+    path: PathBuf,
+    tasks: Vec<Task>,
+}
+
+impl Archive {
+    pub fn path_for(list_path: &Path) -> PathBuf {
+        list_path.with_extension("archive.json")
+    }
+
+    /// Reads the archive, or starts an empty one if the file doesn't exist. An archive
+    /// that can't be read or parsed is an error, so it is never overwritten.
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let tasks = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read archive {}: {}", path.display(), e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid archive {}: {}", path.display(), e))?
+        } else {
+            Vec::new()
+        };
+        Ok(Archive { path, tasks })
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    pub fn extend(&mut self, tasks: impl IntoIterator<Item = Task>) {
+        self.tasks.extend(tasks);
+    }
+
+    /// Drops archived tasks completed before `cutoff`, or all of them without one,
+    /// and returns how many were removed. Tasks archived without a completion time
+    /// fall back to their last modification.
+    pub fn purge(&mut self, cutoff: Option<DateTime<Utc>>) -> usize {
+        let before = self.tasks.len();
+        if let Some(cutoff) = cutoff {
+            self.tasks
+                .retain(|task| task.completed_at.unwrap_or(task.modified_at()) >= cutoff);
+        } else {
+            self.tasks.clear();
+        }
+        before - self.tasks.len()
+    }
+
+    /// Writes the archive via a temp file and rename, like `TodoList::save`.
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.tasks)
+            .map_err(|e| format!("Failed to serialize archive: {}", e))?;
+        let tmp_path = temp_path_for(&self.path);
+        fs::write(&tmp_path, content)
+            .map_err(|e| format!("Failed to write to temp file: {}", e))?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| format!("Failed to rename temp file: {}", e))
+    }
+}
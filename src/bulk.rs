@@ -0,0 +1,107 @@
+use std::str::FromStr;
+
+/// One line of a file given to `add --from-file`: a title, optionally followed by
+/// `| description` and `| category`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskLine {
+    pub title: String,
+    pub description: String,
+    pub category: Option<String>,
+}
+
+impl FromStr for TaskLine {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        let (title, description, category) = match fields.as_slice() {
+            [title] => (*title, "", None),
+            [title, description] => (*title, *description, None),
+            [title, description, category] => (*title, *description, Some(*category)),
+            _ => {
+                return Err(format!(
+                    "Expected 'title | description | category', got {} fields",
+                    fields.len()
+                ))
+            }
+        };
+        if title.is_empty() {
+            return Err("Task title cannot be empty".to_string());
+        }
+        Ok(TaskLine {
+            title: title.to_string(),
+            description: description.to_string(),
+            category: category.filter(|c| !c.is_empty()).map(String::from),
+        })
+    }
+}
+
+/// Parses each line of `content` that isn't blank or a `#` comment, paired with its
+/// 1-based line number.
+pub fn parse_task_lines(content: &str) -> Vec<(usize, Result<TaskLine, String>)> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| (i + 1, line.parse()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Category, Task};
+    use crate::test_support::{cleanup_file, setup};
+
+    const FIXTURE: &str = "\
+# Moving house
+Book movers
+Pack kitchen | Plates and glasses first
+Change address | Bank, insurance | errands
+
+ | missing title
+Too | many | fields | here
+Book movers
+";
+
+    #[test]
+    fn test_parse_task_lines() {
+        let lines = parse_task_lines(FIXTURE);
+        let numbers: Vec<usize> = lines.iter().map(|(n, _)| *n).collect();
+        assert_eq!(numbers, [2, 3, 4, 6, 7, 8]);
+        assert_eq!(
+            lines[2].1,
+            Ok(TaskLine {
+                title: "Change address".to_string(),
+                description: "Bank, insurance".to_string(),
+                category: Some("errands".to_string()),
+            })
+        );
+        assert_eq!(lines[0].1.as_ref().unwrap().description, "");
+        assert!(lines[3].1.is_err());
+        assert!(lines[4].1.as_ref().unwrap_err().contains("4 fields"));
+    }
+
+    #[test]
+    fn test_add_each_reports_per_task() {
+        let (mut todo_list, file_path) = setup();
+        let tasks = parse_task_lines(FIXTURE)
+            .into_iter()
+            .filter_map(|(_, line)| line.ok())
+            .map(|line| {
+                let category = line.category.unwrap_or_else(|| "general".to_string());
+                Task::new(line.title, line.description, Category(category))
+            })
+            .collect();
+        let results = todo_list.add_each(tasks).unwrap();
+        assert_eq!(results.len(), 4);
+        assert!(results[..3].iter().all(Result::is_ok));
+        assert!(results[3].as_ref().unwrap_err().contains("already exists"));
+        assert_eq!(todo_list.len(), 3);
+        assert_eq!(
+            todo_list.get_task("Change address").unwrap().category.0,
+            "errands"
+        );
+        cleanup_file(&file_path);
+    }
+}
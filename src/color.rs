@@ -0,0 +1,138 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Label names accepted by `--label`, with their ANSI foreground codes.
+const NAMED_COLORS: [(&str, u8); 8] = [
+    ("black", 30),
+    ("red", 31),
+    ("green", 32),
+    ("yellow", 33),
+    ("blue", 34),
+    ("magenta", 35),
+    ("cyan", 36),
+    ("white", 37),
+];
+
+/// Validates a color label: one of the named colors or a `#rrggbb` hex code.
+/// Returns the label lowercased.
+pub fn parse_label(s: &str) -> Result<String, String> {
+    let label = s.trim().to_lowercase();
+    let is_named = NAMED_COLORS.iter().any(|(name, _)| *name == label);
+    if is_named || hex_rgb(&label).is_some() {
+        Ok(label)
+    } else {
+        let names: Vec<&str> = NAMED_COLORS.iter().map(|(name, _)| *name).collect();
+        Err(format!(
+            "Unknown label '{}': expected one of {} or a hex code like #ff8800",
+            s,
+            names.join(", ")
+        ))
+    }
+}
+
+fn hex_rgb(label: &str) -> Option<(u8, u8, u8)> {
+    let hex = label.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Wraps `text` in ANSI escape codes for the label's color. Unknown labels are
+/// returned unchanged.
+pub fn colorize(text: &str, label: &str) -> String {
+    let code = match NAMED_COLORS.iter().find(|(name, _)| *name == label) {
+        Some((_, code)) => code.to_string(),
+        None => match hex_rgb(label) {
+            Some((r, g, b)) => format!("38;2;{};{};{}", r, g, b),
+            None => return text.to_string(),
+        },
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+/// When to color output, as given to `--color`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color only when writing to a terminal and `NO_COLOR` is unset.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self, is_terminal: bool, no_color_env: bool) -> bool {
+        match self {
+            ColorChoice::Auto => is_terminal && !no_color_env,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorChoice::Auto => write!(f, "auto"),
+            ColorChoice::Always => write!(f, "always"),
+            ColorChoice::Never => write!(f, "never"),
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "Invalid color choice '{}': expected auto, always or never",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_choice() {
+        assert_eq!("auto".parse(), Ok(ColorChoice::Auto));
+        assert_eq!("Always".parse(), Ok(ColorChoice::Always));
+        assert_eq!("never".parse(), Ok(ColorChoice::Never));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+
+        assert!(ColorChoice::Auto.enabled(true, false));
+        assert!(!ColorChoice::Auto.enabled(true, true));
+        assert!(!ColorChoice::Auto.enabled(false, false));
+        assert!(ColorChoice::Always.enabled(false, true));
+        assert!(!ColorChoice::Never.enabled(true, false));
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(parse_label("red"), Ok("red".to_string()));
+        assert_eq!(parse_label(" Blue "), Ok("blue".to_string()));
+        assert_eq!(parse_label("#FF8800"), Ok("#ff8800".to_string()));
+        assert!(parse_label("purple").is_err());
+        assert!(parse_label("#ff88").is_err());
+        assert!(parse_label("#gg8800").is_err());
+    }
+
+    #[test]
+    fn test_colorize() {
+        assert_eq!(colorize("Task", "red"), "\x1b[31mTask\x1b[0m");
+        assert_eq!(
+            colorize("Task", "#ff8800"),
+            "\x1b[38;2;255;136;0mTask\x1b[0m"
+        );
+        assert_eq!(colorize("Task", "purple"), "Task");
+    }
+}
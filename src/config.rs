@@ -0,0 +1,228 @@
+use crate::date::{check_date_format, parse_duration};
+use crate::task::{Category, Priority};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FALLBACK_CATEGORY: &str = "general";
+
+/// User settings read from `$XDG_CONFIG_HOME/todo/config.toml`
+/// (or `~/.config/todo/config.toml`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub default_category: Option<String>,
+    /// How many rotating backups (`tasks.json.1` being the newest) to keep on
+    /// every save. 0, the default, disables them.
+    pub backups: usize,
+    /// Lowercase category names on `add`, so `Work` and `work` are the same category.
+    pub lowercase_categories: bool,
+    /// If non-empty, `add` rejects any category not in this list.
+    pub categories: Vec<String>,
+    /// Per-category defaults for `add`, keyed by category name.
+    pub category_defaults: HashMap<String, CategoryDefaults>,
+    /// Share of words (0.0 to 1.0) a new task must have in common with an existing
+    /// one in its category for `add` to warn about a likely duplicate.
+    pub duplicate_threshold: Option<f64>,
+    /// strftime-style format for entering and showing dates, e.g. `%d/%m/%Y %H:%M`.
+    pub date_format: Option<String>,
+}
+
+/// What `add` uses for a category when the field isn't given on the command line.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CategoryDefaults {
+    #[serde(deserialize_with = "deserialize_priority")]
+    pub priority: Option<Priority>,
+    /// How long after the task's creation date it falls due, e.g. `1d`.
+    #[serde(deserialize_with = "deserialize_offset")]
+    pub due: Option<Duration>,
+}
+
+fn deserialize_priority<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Priority>, D::Error> {
+    Option::<String>::deserialize(d)?
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn deserialize_offset<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Duration>, D::Error> {
+    Option::<String>::deserialize(d)?
+        .map(|s| parse_duration(&s).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        let base = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("todo").join("config.toml"))
+    }
+
+    /// Loads the config from `path`, falling back to defaults if the file doesn't exist.
+    pub fn load_from(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+
+    pub fn load() -> Self {
+        let Some(path) = Config::path() else {
+            return Config::default();
+        };
+        let config = Config::load_from(&path).unwrap_or_else(|e| {
+            eprintln!("Warning: {}", e);
+            Config::default()
+        });
+        if let Some(Err(e)) = config.date_format.as_deref().map(check_date_format) {
+            eprintln!("Warning: {} (in {})", e, path.display());
+        }
+        config
+    }
+
+    pub fn default_category(&self) -> &str {
+        self.default_category
+            .as_deref()
+            .unwrap_or(FALLBACK_CATEGORY)
+    }
+
+    fn normalize_category(&self, name: &str) -> Result<Category, String> {
+        let category: Category = name.parse()?;
+        Ok(if self.lowercase_categories {
+            category.to_lowercase()
+        } else {
+            category
+        })
+    }
+
+    /// Parses and normalizes `name`, checking it against the `categories` whitelist.
+    pub fn category(&self, name: &str) -> Result<Category, String> {
+        let category = self.normalize_category(name)?;
+        if self.categories.is_empty()
+            || self
+                .categories
+                .iter()
+                .any(|allowed| self.normalize_category(allowed).as_ref() == Ok(&category))
+        {
+            Ok(category)
+        } else {
+            Err(format!(
+                "Unknown category '{}'. Allowed categories: {}",
+                category,
+                self.categories.join(", ")
+            ))
+        }
+    }
+
+    /// Resolves the priority and due date of a new task in `category` created at
+    /// `created`. An explicit value wins, then the category's default, then the global
+    /// default: medium priority and no due date.
+    pub fn task_defaults(
+        &self,
+        category: &Category,
+        priority: Option<Priority>,
+        due: Option<DateTime<Utc>>,
+        created: DateTime<Utc>,
+    ) -> (Priority, Option<DateTime<Utc>>) {
+        let defaults = self
+            .category_defaults
+            .iter()
+            .find(|(name, _)| self.normalize_category(name).as_ref() == Ok(category))
+            .map(|(_, defaults)| defaults.clone())
+            .unwrap_or_default();
+        let priority = priority.or(defaults.priority).unwrap_or_default();
+        let due = due.or_else(|| defaults.due.map(|offset| created + offset));
+        (priority, due)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::cleanup_file;
+
+    #[test]
+    fn test_config_load() {
+        let missing = PathBuf::from("test_config_missing.toml");
+        let config = Config::load_from(&missing).unwrap();
+        assert_eq!(config.default_category(), "general");
+        assert_eq!(config.backups, 0);
+
+        let path = PathBuf::from("test_config_default_category.toml");
+        fs::write(
+            &path,
+            "default_category = \"work\"\nbackups = 3\nduplicate_threshold = 0.8\n",
+        )
+        .unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.default_category(), "work");
+        assert_eq!(config.backups, 3);
+        assert_eq!(config.duplicate_threshold, Some(0.8));
+
+        fs::write(&path, "default_category = [").unwrap();
+        assert!(Config::load_from(&path).is_err());
+
+        fs::write(
+            &path,
+            "lowercase_categories = true\ncategories = [\"Work\", \"home\"]\n",
+        )
+        .unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.category(" WORK ").unwrap().0, "work");
+        assert_eq!(config.category("Home").unwrap().0, "home");
+        assert!(config.category("errands").is_err());
+        assert!(config.category(" ").is_err());
+
+        fs::write(&path, "").unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.category(" Errands ").unwrap().0, "Errands");
+        cleanup_file(&path);
+    }
+
+    #[test]
+    fn test_task_defaults_precedence() {
+        let path = PathBuf::from("test_config_category_defaults.toml");
+        fs::write(
+            &path,
+            "lowercase_categories = true\n\
+             [category_defaults.Urgent]\npriority = \"high\"\ndue = \"1d\"\n\
+             [category_defaults.someday]\npriority = \"low\"\n",
+        )
+        .unwrap();
+        let config = Config::load_from(&path).unwrap();
+        let created = Utc::now();
+        let urgent = config.category("urgent").unwrap();
+        let someday = config.category("someday").unwrap();
+        let other = config.category("other").unwrap();
+
+        assert_eq!(
+            config.task_defaults(&urgent, None, None, created),
+            (Priority::High, Some(created + Duration::days(1)))
+        );
+        let due = created + Duration::weeks(2);
+        assert_eq!(
+            config.task_defaults(&urgent, Some(Priority::Low), Some(due), created),
+            (Priority::Low, Some(due))
+        );
+        assert_eq!(
+            config.task_defaults(&someday, None, None, created),
+            (Priority::Low, None)
+        );
+        assert_eq!(
+            config.task_defaults(&other, None, None, created),
+            (Priority::Medium, None)
+        );
+
+        fs::write(&path, "[category_defaults.urgent]\npriority = \"asap\"\n").unwrap();
+        assert!(Config::load_from(&path).is_err());
+        fs::write(&path, "[category_defaults.urgent]\ndue = \"tomorrow\"\n").unwrap();
+        assert!(Config::load_from(&path).is_err());
+        cleanup_file(&path);
+    }
+}
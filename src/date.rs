@@ -0,0 +1,383 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use serde::{de, Deserialize, Deserializer};
+use std::sync::OnceLock;
+
+/// Time zone dates are displayed in, if overridden with `set_display_timezone`.
+static DISPLAY_TZ: OnceLock<Tz> = OnceLock::new();
+
+/// strftime-style format dates are parsed and displayed with, if set with
+/// `set_date_format`.
+static DATE_FORMAT: OnceLock<String> = OnceLock::new();
+
+/// Parses `s` with a strftime-style format that has a date and optionally a time
+/// (midnight if it has none).
+fn parse_with_format(s: &str, format: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, format)
+        .or_else(|_| NaiveDate::parse_from_str(s, format).map(|d| d.and_time(NaiveTime::MIN)))
+        .ok()
+}
+
+/// Parses a date given on the command line. Accepted forms, tried in order: the
+/// format given to `set_date_format` if any (local time), then `YYYY-MM-DD HH:MM`
+/// (local time), RFC3339 with an offset, ISO 8601 without one
+/// (`2024-01-02T15:04:05`, local time) and `YYYY-MM-DD` (local midnight).
+pub fn parse_date(date_str: &str) -> Result<DateTime<Utc>, String> {
+    let s = date_str.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    let custom = DATE_FORMAT
+        .get()
+        .and_then(|format| parse_with_format(s, format));
+    let naive = custom
+        .ok_or(())
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M"))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| d.and_time(NaiveTime::MIN)))
+        .map_err(|_| {
+            let custom = DATE_FORMAT
+                .get()
+                .map(|format| format!("{}, ", format))
+                .unwrap_or_default();
+            format!(
+                "Invalid date '{}': expected {}YYYY-MM-DD HH:MM, YYYY-MM-DD, or ISO 8601/RFC3339 \
+                 such as 2024-01-02T15:04:05 or 2024-01-02T15:04:05Z",
+                date_str, custom
+            )
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|date| date.with_timezone(&Utc))
+        .ok_or_else(|| format!("Invalid local time '{}'", date_str))
+}
+
+/// Parses a relative duration such as `30m`, `24h`, `7d` or `2w`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let invalid = || {
+        format!(
+            "Invalid duration '{}': expected a number followed by m, h, d or w (e.g. 7d)",
+            s
+        )
+    };
+    let unit = s.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = s[..s.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    if amount < 0 {
+        return Err(invalid());
+    }
+    match unit {
+        'm' => Ok(Duration::minutes(amount)),
+        'h' => Ok(Duration::hours(amount)),
+        'd' => Ok(Duration::days(amount)),
+        'w' => Ok(Duration::weeks(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses either a relative duration (`30d`, meaning that long before now) or a
+/// `YYYY-MM-DD HH:MM` timestamp into an absolute cutoff.
+pub fn parse_cutoff(s: &str) -> Result<DateTime<Utc>, String> {
+    match parse_duration(s) {
+        Ok(duration) => Ok(Utc::now() - duration),
+        Err(_) => parse_date(s).map_err(|_| {
+            format!(
+                "Invalid cutoff '{}': expected a duration like 30d or a date like 2024-01-31 00:00",
+                s
+            )
+        }),
+    }
+}
+
+/// Renders the offset of a moment from now in words: negative deltas are in the past
+/// ("3 days ago"), positive ones in the future ("in 2 hours"). Only the largest whole
+/// unit is kept; anything under a minute is "just now".
+pub fn humanize(delta: Duration) -> String {
+    const UNITS: [(&str, i64); 6] = [
+        ("year", 365 * 86_400),
+        ("month", 30 * 86_400),
+        ("week", 7 * 86_400),
+        ("day", 86_400),
+        ("hour", 3_600),
+        ("minute", 60),
+    ];
+    let seconds = delta.num_seconds();
+    let Some((unit, count)) = UNITS
+        .iter()
+        .map(|&(unit, size)| (unit, seconds.abs() / size))
+        .find(|&(_, count)| count > 0)
+    else {
+        return "just now".to_string();
+    };
+    let amount = format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" });
+    if seconds < 0 {
+        format!("{} ago", amount)
+    } else {
+        format!("in {}", amount)
+    }
+}
+
+/// Parses an IANA time zone name such as `America/New_York`.
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse()
+        .map_err(|_| format!("Unknown time zone '{}'", name))
+}
+
+/// Makes `to_local` display dates in `tz` instead of the machine's time zone.
+/// Only the first call has an effect.
+pub fn set_display_timezone(tz: Tz) {
+    let _ = DISPLAY_TZ.set(tz);
+}
+
+/// Dates are stored in UTC and only converted to local time for display, in the
+/// time zone given to `set_display_timezone` if there was one.
+pub fn to_local(date: DateTime<Utc>) -> DateTime<FixedOffset> {
+    match DISPLAY_TZ.get() {
+        Some(tz) => date.with_timezone(tz).fixed_offset(),
+        None => date.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+/// Makes `parse_date` accept, and `format_date` display, dates in a strftime-style
+/// `format` such as `%d/%m/%Y %H:%M`. Fails if the format has an unknown specifier.
+/// Only the first successful call has an effect.
+pub fn set_date_format(format: &str) -> Result<(), String> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(format!("Invalid date format '{}'", format));
+    }
+    let _ = DATE_FORMAT.set(format.to_string());
+    Ok(())
+}
+
+/// Checks that dates displayed with `format` can be parsed back to the same text, so
+/// that dates printed by one command can be typed into another.
+pub fn check_date_format(format: &str) -> Result<(), String> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(format!("Invalid date format '{}'", format));
+    }
+    // The 31st of the 1st month, so day and month can't be mixed up unnoticed.
+    let sample = NaiveDate::from_ymd_opt(2024, 1, 31)
+        .and_then(|date| date.and_hms_opt(13, 45, 0))
+        .unwrap();
+    let text = sample.format(format).to_string();
+    match parse_with_format(&text, format) {
+        Some(parsed) if parsed.format(format).to_string() == text => Ok(()),
+        _ => Err(format!(
+            "Date format '{}' does not round-trip: '{}' can't be parsed back, so dates \
+             shown with it can't be used as input",
+            format, text
+        )),
+    }
+}
+
+/// A date as shown to the user: in local time (see `to_local`), with the format
+/// given to `set_date_format` if there was one.
+pub fn format_date(date: DateTime<Utc>) -> String {
+    match DATE_FORMAT.get() {
+        Some(format) => to_local(date).format(format).to_string(),
+        None => to_local(date).to_string(),
+    }
+}
+
+/// Reads a stored timestamp. Current files use RFC3339 in UTC; older files may carry a
+/// different offset (converted to UTC) or none at all, in which case the timestamp is
+/// reinterpreted as local time.
+fn parse_stored_date(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .map_err(|e| format!("Invalid timestamp '{}': {}", s, e))?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|date| date.with_timezone(&Utc))
+        .ok_or_else(|| format!("Invalid local timestamp '{}'", s))
+}
+
+pub fn deserialize_utc<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse_stored_date(&s).map_err(de::Error::custom)
+}
+
+pub fn deserialize_opt_utc<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| parse_stored_date(&s))
+        .transpose()
+        .map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn test_humanize() {
+        assert_eq!(humanize(Duration::zero()), "just now");
+        assert_eq!(humanize(Duration::seconds(-59)), "just now");
+        assert_eq!(humanize(Duration::minutes(-1)), "1 minute ago");
+        assert_eq!(humanize(Duration::minutes(45)), "in 45 minutes");
+        assert_eq!(
+            humanize(Duration::hours(2) + Duration::minutes(59)),
+            "in 2 hours"
+        );
+        assert_eq!(humanize(Duration::days(-3)), "3 days ago");
+        assert_eq!(humanize(Duration::days(-13)), "1 week ago");
+        assert_eq!(humanize(Duration::days(60)), "in 2 months");
+        assert_eq!(humanize(Duration::days(-800)), "2 years ago");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30m"), Ok(Duration::minutes(30)));
+        assert_eq!(parse_duration("24h"), Ok(Duration::hours(24)));
+        assert_eq!(parse_duration("7d"), Ok(Duration::days(7)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::weeks(2)));
+        assert_eq!(parse_duration(" 0d "), Ok(Duration::zero()));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("7y").is_err());
+        assert!(parse_duration("-1d").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        assert!(parse_duration("5é").is_err());
+    }
+
+    #[test]
+    fn test_parse_date_formats() {
+        let local = |y, mo, d, h, mi, s| {
+            Local
+                .with_ymd_and_hms(y, mo, d, h, mi, s)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        assert_eq!(
+            parse_date("2024-01-02 15:04"),
+            Ok(local(2024, 1, 2, 15, 4, 0))
+        );
+        assert_eq!(
+            parse_date("2024-01-02T15:04:05"),
+            Ok(local(2024, 1, 2, 15, 4, 5))
+        );
+        assert_eq!(parse_date("2024-01-02"), Ok(local(2024, 1, 2, 0, 0, 0)));
+        assert_eq!(
+            parse_date("2024-01-02T15:04:05Z"),
+            Ok(Utc.with_ymd_and_hms(2024, 1, 2, 15, 4, 5).unwrap())
+        );
+        assert_eq!(
+            parse_date("2024-01-02T15:04:05+02:00"),
+            Ok(Utc.with_ymd_and_hms(2024, 1, 2, 13, 4, 5).unwrap())
+        );
+        let err = parse_date("02/01/2024").unwrap_err();
+        assert!(err.contains("YYYY-MM-DD HH:MM"));
+    }
+
+    #[test]
+    fn test_parse_cutoff() {
+        let cutoff = parse_cutoff("7d").unwrap();
+        let expected = Utc::now() - Duration::days(7);
+        assert!((expected - cutoff).num_seconds().abs() < 5);
+        assert_eq!(
+            parse_cutoff("2024-01-31 00:00"),
+            Ok(parse_date("2024-01-31 00:00").unwrap())
+        );
+        assert!(parse_cutoff("last week").is_err());
+    }
+
+    #[test]
+    fn test_stored_dates_are_utc() {
+        let instant = Utc.with_ymd_and_hms(2024, 1, 2, 15, 4, 5).unwrap();
+        assert_eq!(
+            serde_json::to_string(&instant).unwrap(),
+            "\"2024-01-02T15:04:05Z\""
+        );
+        assert_eq!(parse_stored_date("2024-01-02T17:04:05+02:00"), Ok(instant));
+        assert_eq!(parse_stored_date("2024-01-02T10:04:05-05:00"), Ok(instant));
+    }
+
+    #[test]
+    fn test_legacy_naive_dates_are_local() {
+        let naive =
+            NaiveDateTime::parse_from_str("2024-01-02 15:04:05", "%Y-%m-%d %H:%M:%S").unwrap();
+        let expected = Local
+            .from_local_datetime(&naive)
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_stored_date("2024-01-02T15:04:05"), Ok(expected));
+        assert!(parse_stored_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_display_is_consistent_across_timezones() {
+        let written_in_berlin = parse_stored_date("2024-01-02T16:04:05+01:00").unwrap();
+        let written_in_new_york = parse_stored_date("2024-01-02T10:04:05-05:00").unwrap();
+        assert_eq!(written_in_berlin, written_in_new_york);
+
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        assert_eq!(
+            written_in_berlin.with_timezone(&tokyo).to_string(),
+            written_in_new_york.with_timezone(&tokyo).to_string()
+        );
+        assert_eq!(to_local(written_in_berlin), to_local(written_in_new_york));
+    }
+
+    #[test]
+    fn test_check_date_format() {
+        assert!(check_date_format("%d/%m/%Y %H:%M").is_ok());
+        assert!(check_date_format("%d.%m.%Y").is_ok());
+        assert!(check_date_format("%m/%d/%Y %I:%M %p").is_ok());
+        let lossy = check_date_format("%d/%m %H:%M").unwrap_err();
+        assert!(lossy.contains("'31/01 13:45'"), "{}", lossy);
+        assert!(check_date_format("%Y-%m-%d %H").is_err());
+        assert!(check_date_format("%d/%m/%Y %Q")
+            .unwrap_err()
+            .starts_with("Invalid date format"));
+    }
+
+    #[test]
+    fn test_parse_with_locale_formats() {
+        let expected = NaiveDate::from_ymd_opt(2024, 1, 31)
+            .unwrap()
+            .and_hms_opt(13, 45, 0);
+        assert_eq!(
+            parse_with_format("31/01/2024 13:45", "%d/%m/%Y %H:%M"),
+            expected
+        );
+        assert_eq!(
+            parse_with_format("01/31/2024 01:45 PM", "%m/%d/%Y %I:%M %p"),
+            expected
+        );
+        assert_eq!(
+            parse_with_format("31.01.2024", "%d.%m.%Y"),
+            NaiveDate::from_ymd_opt(2024, 1, 31)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(parse_with_format("2024-01-31", "%d/%m/%Y"), None);
+    }
+
+    #[test]
+    fn test_parse_timezone() {
+        let new_york = parse_timezone("America/New_York").unwrap();
+        let date = parse_stored_date("2024-01-02T15:04:05Z").unwrap();
+        assert_eq!(
+            date.with_timezone(&new_york).fixed_offset().to_string(),
+            "2024-01-02 10:04:05 -05:00"
+        );
+        assert!(parse_timezone(" Europe/Berlin ").is_ok());
+        assert!(parse_timezone("Mars/Olympus_Mons").is_err());
+    }
+}
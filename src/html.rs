@@ -0,0 +1,112 @@
+use crate::date::format_date;
+use crate::task::{Task, TaskStatus};
+use crate::todo_list::{group_tasks, GroupBy};
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; width: 100%; margin: 0.5em 0 1.5em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f0f0f0; }
+tr.on { background: #fff8e1; }
+tr.done { background: #e8f5e9; color: #666; }
+summary { font-size: 1.2em; font-weight: bold; cursor: pointer; }";
+
+/// Escapes text for use in HTML element content and attribute values.
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders tasks as a self-contained HTML page for sharing: a collapsible section
+/// per category, each holding a table whose rows are colored by status. All user
+/// text is escaped.
+pub fn render_html(tasks: Vec<&Task>) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Tasks</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>Tasks</h1>\n",
+        STYLE
+    );
+    if tasks.is_empty() {
+        out.push_str("<p>No tasks found.</p>\n");
+    }
+    for (category, tasks) in group_tasks(tasks, GroupBy::Category) {
+        out.push_str(&format!(
+            "<details open>\n<summary>{} ({})</summary>\n<table>\n\
+             <tr><th>Title</th><th>Description</th><th>Status</th><th>Priority</th>\
+             <th>Created</th><th>Due</th></tr>\n",
+            escape_html(&category),
+            tasks.len()
+        ));
+        for task in tasks {
+            let class = match task.status {
+                TaskStatus::Active => "on",
+                TaskStatus::Done => "done",
+            };
+            let cells = [
+                task.title.clone(),
+                task.description.clone(),
+                task.status.to_string(),
+                task.priority.to_string(),
+                format_date(task.creation_date),
+                task.due.map(format_date).unwrap_or_default(),
+            ];
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|cell| format!("<td>{}</td>", escape_html(cell)))
+                .collect();
+            out.push_str(&format!(
+                "<tr class=\"{}\">{}</tr>\n",
+                class,
+                cells.concat()
+            ));
+        }
+        out.push_str("</table>\n</details>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use crate::task::Category;
+    use crate::todo_list::TodoList;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_export_html_escapes_user_text() {
+        let storage = MemoryStorage::default();
+        let mut todo_list =
+            TodoList::with_storage(PathBuf::from("memory.json"), Box::new(storage)).unwrap();
+        for (title, description, category) in [
+            ("Fix <br> tags", "Check that a < b && b > c", "web & mobile"),
+            ("Ship", "Say \"hi\"", "work"),
+        ] {
+            let task = Task::new(
+                title.to_string(),
+                description.to_string(),
+                Category(category.to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        todo_list.mark_as_done("Ship").unwrap();
+
+        let html = todo_list.export_html();
+        assert!(html.contains("<td>Check that a &lt; b &amp;&amp; b &gt; c</td>"));
+        assert!(html.contains("<td>Fix &lt;br&gt; tags</td>"));
+        assert!(html.contains("<summary>web &amp; mobile (1)</summary>"));
+        assert!(html.contains("<tr class=\"done\"><td>Ship</td><td>Say &quot;hi&quot;</td>"));
+        assert!(!html.contains("<br>"));
+        assert!(html.find("web &amp; mobile").unwrap() < html.find("work (1)").unwrap());
+    }
+}
@@ -0,0 +1,47 @@
+use std::io::{self, Write};
+
+use crate::task::Task;
+
+/// Writes tasks as a JSON array, one task per line, serializing each task straight
+/// to `writer` so the whole document is never held in memory.
+pub fn write_json_array<'a>(
+    tasks: impl IntoIterator<Item = &'a Task>,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    writer.write_all(b"[")?;
+    for (i, task) in tasks.into_iter().enumerate() {
+        writer.write_all(if i == 0 { b"\n" } else { b",\n" })?;
+        serde_json::to_writer(&mut writer, task)?;
+    }
+    writer.write_all(b"\n]\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Category;
+
+    #[test]
+    fn test_write_json_array() {
+        let tasks: Vec<Task> = ["First", "Second"]
+            .iter()
+            .map(|title| {
+                Task::new(
+                    title.to_string(),
+                    "Line one\nline \"two\"".to_string(),
+                    Category("TestCategory".to_string()),
+                )
+            })
+            .collect();
+        let mut out = Vec::new();
+        write_json_array(&tasks, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), 4);
+        assert_eq!(serde_json::from_str::<Vec<Task>>(&text).unwrap(), tasks);
+
+        let mut out = Vec::new();
+        write_json_array(&[], &mut out).unwrap();
+        assert_eq!(serde_json::from_slice::<Vec<Task>>(&out).unwrap(), []);
+    }
+}
@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::storage::{temp_path_for, Storage};
+use crate::task::Task;
+
+/// Journals shorter than this are never compacted.
+const MIN_COMPACTION_RECORDS: usize = 64;
+/// Compact once the journal holds this many records per live task.
+const COMPACTION_RATIO: usize = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Record {
+    Put { task: Box<Task> },
+    Delete { title: String },
+}
+
+/// Append-only JSON Lines store: one record per line, either the full new state of a task
+/// (`put`) or a tombstone for a deleted title (`delete`). Loading replays every line in
+/// order, so the last record for a title wins.
+#[derive(Debug)]
+pub(crate) struct JsonlJournal {
+    path: PathBuf,
+    /// Tasks as of the last write, used to work out which records to append.
+    persisted: HashMap<String, Task>,
+    records: usize,
+}
+
+/// Whether the file's last byte is something other than a newline, e.g. after a crash
+/// tore the last record. Missing and empty files count as ending cleanly.
+fn ends_mid_line(path: &Path) -> std::io::Result<bool> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
+
+impl JsonlJournal {
+    pub(crate) fn new(path: &Path) -> Self {
+        JsonlJournal {
+            path: path.to_path_buf(),
+            persisted: HashMap::new(),
+            records: 0,
+        }
+    }
+
+    fn compact(&mut self, ordered: &BTreeMap<&String, &Task>) -> Result<(), String> {
+        let mut content = String::new();
+        for task in ordered.values() {
+            let record = Record::Put {
+                task: Box::new((*task).clone()),
+            };
+            content.push_str(&serde_json::to_string(&record).map_err(|e| e.to_string())?);
+            content.push('\n');
+        }
+        let tmp_path = temp_path_for(&self.path);
+        fs::write(&tmp_path, content)
+            .map_err(|e| format!("Failed to write to temp file: {}", e))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("Failed to rename temp file: {}", e))?;
+        self.records = ordered.len();
+        Ok(())
+    }
+}
+
+impl Storage for JsonlJournal {
+    /// Replays the journal. Lines that fail to parse (such as a line torn by a crash
+    /// mid-append) are skipped rather than discarding the whole list.
+    fn load(&mut self) -> Result<HashMap<String, Task>, String> {
+        let mut tasks = HashMap::new();
+        let mut records = 0;
+        if self.path.exists() {
+            let content = fs::read_to_string(&self.path)
+                .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+            for record in content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<Record>(line).ok())
+            {
+                records += 1;
+                match record {
+                    Record::Put { task } => {
+                        tasks.insert(task.title.clone(), *task);
+                    }
+                    Record::Delete { title } => {
+                        tasks.remove(&title);
+                    }
+                }
+            }
+        }
+        self.persisted = tasks.clone();
+        self.records = records;
+        Ok(tasks)
+    }
+
+    /// Appends a record for every task that changed since the last write, first ending
+    /// any torn last line so the new records aren't glued onto it. Once tombstones
+    /// and superseded records outnumber live tasks by `COMPACTION_RATIO`, the file is
+    /// rewritten with a single `put` per task instead.
+    fn save(&mut self, tasks: &HashMap<String, Task>) -> Result<(), String> {
+        let ordered: BTreeMap<&String, &Task> = tasks.iter().collect();
+        let mut changes: Vec<Record> = ordered
+            .iter()
+            .filter(|(title, task)| self.persisted.get(**title) != Some(**task))
+            .map(|(_, task)| Record::Put {
+                task: Box::new((*task).clone()),
+            })
+            .collect();
+        let mut removed: Vec<&String> = self
+            .persisted
+            .keys()
+            .filter(|title| !tasks.contains_key(*title))
+            .collect();
+        removed.sort();
+        changes.extend(removed.into_iter().map(|title| Record::Delete {
+            title: title.clone(),
+        }));
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let records = self.records + changes.len();
+        if records > MIN_COMPACTION_RECORDS && records > COMPACTION_RATIO * tasks.len() {
+            self.compact(&ordered)?;
+        } else {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(|e| format!("Failed to open journal: {}", e))?;
+            if ends_mid_line(&self.path).map_err(|e| format!("Failed to read journal: {}", e))? {
+                writeln!(file).map_err(|e| format!("Failed to append to journal: {}", e))?;
+            }
+            for record in &changes {
+                let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+                writeln!(file, "{}", line)
+                    .map_err(|e| format!("Failed to append to journal: {}", e))?;
+            }
+            self.records = records;
+        }
+        self.persisted = tasks.clone();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Category, TaskStatus};
+    use crate::test_support::{cleanup_file, get_unique_file_path};
+    use crate::todo_list::{StoreFormat, TodoList};
+
+    fn line_count(path: &Path) -> usize {
+        fs::read_to_string(path).unwrap().lines().count()
+    }
+
+    #[test]
+    fn test_jsonl_round_trip() {
+        let file_path = get_unique_file_path().with_extension("jsonl");
+        let mut todo_list = TodoList::with_format(file_path.clone(), StoreFormat::Jsonl).unwrap();
+        for title in ["Task 1", "Task 2", "Task 3"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        assert_eq!(line_count(&file_path), 3);
+
+        todo_list.mark_as_done("Task 1").unwrap();
+        todo_list.delete_task("Task 2").unwrap();
+        assert_eq!(line_count(&file_path), 5);
+
+        let reloaded = TodoList::with_format(file_path.clone(), StoreFormat::Jsonl).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(
+            reloaded.get_task("Task 1").unwrap().status,
+            TaskStatus::Done
+        );
+        assert!(reloaded.get_task("Task 2").is_none());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_jsonl_skips_torn_lines() {
+        let file_path = get_unique_file_path().with_extension("jsonl");
+        let mut todo_list = TodoList::with_format(file_path.clone(), StoreFormat::Jsonl).unwrap();
+        let task = Task::new(
+            "Task 1".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&file_path).unwrap();
+        write!(file, "{{\"op\":\"put\",\"task\":{{\"title\"").unwrap();
+
+        let reloaded = TodoList::with_format(file_path.clone(), StoreFormat::Jsonl).unwrap();
+        assert_eq!(reloaded.len(), 1);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_jsonl_appends_after_torn_line() {
+        let file_path = get_unique_file_path().with_extension("jsonl");
+        let mut todo_list = TodoList::with_format(file_path.clone(), StoreFormat::Jsonl).unwrap();
+        let task = |title: &str| {
+            Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            )
+        };
+        todo_list.add_task(task("Task A")).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&file_path).unwrap();
+        write!(file, "{{\"op\":\"put\",\"task\":{{\"title\"").unwrap();
+
+        let mut reloaded = TodoList::with_format(file_path.clone(), StoreFormat::Jsonl).unwrap();
+        reloaded.add_task(task("Task B")).unwrap();
+        let reloaded = TodoList::with_format(file_path.clone(), StoreFormat::Jsonl).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert!(reloaded.get_task("Task B").is_some());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_jsonl_compaction() {
+        let file_path = get_unique_file_path().with_extension("jsonl");
+        let mut todo_list = TodoList::with_format(file_path.clone(), StoreFormat::Jsonl).unwrap();
+        let task = Task::new(
+            "Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        for _ in 0..MIN_COMPACTION_RECORDS {
+            todo_list.add_note("Task", "Progress").unwrap();
+        }
+        assert!(line_count(&file_path) <= MIN_COMPACTION_RECORDS);
+
+        let reloaded = TodoList::with_format(file_path.clone(), StoreFormat::Jsonl).unwrap();
+        assert_eq!(
+            reloaded.get_task("Task").unwrap().notes.len(),
+            MIN_COMPACTION_RECORDS
+        );
+        cleanup_file(&file_path);
+    }
+}
@@ -0,0 +1,81 @@
+pub mod archive;
+pub mod bulk;
+pub mod color;
+pub mod config;
+pub mod date;
+pub mod html;
+pub mod json;
+mod jsonl;
+pub mod predicate;
+pub mod query;
+pub mod recurrence;
+pub mod seed;
+pub mod sort;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod storage;
+pub mod table;
+pub mod task;
+pub mod task_template;
+pub mod template;
+pub mod todo_list;
+pub mod tsv;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod validate;
+
+pub use archive::Archive;
+pub use bulk::{parse_task_lines, TaskLine};
+pub use color::{colorize, parse_label, ColorChoice};
+pub use config::Config;
+pub use date::{
+    check_date_format, format_date, humanize, parse_cutoff, parse_date, parse_duration,
+    parse_timezone, set_date_format, set_display_timezone, to_local,
+};
+pub use html::render_html;
+pub use json::write_json_array;
+pub use predicate::{parse_predicates, Predicate};
+pub use query::QueryStore;
+pub use recurrence::{RecurUnit, Recurrence};
+pub use seed::sample_tasks;
+pub use sort::{compare, sort_tasks, SortKey};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+pub use storage::{JsonFileStorage, MemoryStorage, Storage};
+pub use table::{render_table, TableStyle};
+pub use task::{Category, Note, Priority, Subtask, Task, TaskBuilder, TaskPatch, TaskStatus};
+pub use task_template::{TaskTemplate, TemplateOverrides, TemplateStore};
+pub use template::{expand_env, render_template, truncate_str};
+pub use todo_list::{
+    group_tasks, ConflictStrategy, GroupBy, MergeReport, StoreFormat, TaskStats, TodoList,
+    DEFAULT_SIMILARITY_THRESHOLD, STDIO_PATH,
+};
+pub use tsv::render_tsv;
+pub use validate::{Severity, ValidationIssue};
+
+#[cfg(test)]
+mod test_support {
+    use crate::TodoList;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    pub fn get_unique_file_path() -> PathBuf {
+        let counter = COUNTER.fetch_add(1, Ordering::SeqCst);
+        PathBuf::from(format!("test_tasks_{}.json", counter))
+    }
+
+    pub fn cleanup_file(path: &PathBuf) {
+        if path.exists() {
+            fs::remove_file(path).expect("Failed to remove test file");
+        }
+    }
+
+    pub fn setup() -> (TodoList, PathBuf) {
+        let file_path = get_unique_file_path();
+        let todo_list = TodoList::new(file_path.clone());
+        (todo_list, file_path)
+    }
+}
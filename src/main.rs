@@ -1,281 +1,1212 @@
-use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
-use clap::{Parser, Subcommand};
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs;
-use std::path::PathBuf;
-use std::str::FromStr;
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub enum TaskStatus {
-    Active,
-    Done,
-}
-
-impl std::fmt::Display for TaskStatus {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+use chrono::{DateTime, Duration, Local, NaiveDate, SubsecRound, TimeZone, Utc};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use todo_list::{
+    check_date_format, colorize, expand_env, format_date, group_tasks, humanize, parse_cutoff,
+    parse_date, parse_duration, parse_label, parse_task_lines, parse_timezone, render_html,
+    render_table, render_template, render_tsv, sample_tasks, set_date_format, set_display_timezone,
+    sort_tasks, truncate_str, write_json_array, Category, ColorChoice, Config, ConflictStrategy,
+    GroupBy, Predicate, Priority, QueryStore, Recurrence, Severity, SortKey, StoreFormat,
+    TableStyle, Task, TaskPatch, TaskStatus, TaskTemplate, TemplateOverrides, TemplateStore,
+    TodoList, STDIO_PATH,
+};
+
+#[derive(Parser)]
+#[command(name = "todo")]
+#[command(about = "A simple TODO list CLI application", long_about = None)]
+struct Cli {
+    /// Suppress success messages (errors and query output are still printed)
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    /// How the task list is stored: "json" (tasks.json), "jsonl" (tasks.jsonl) or, with the
+    /// `sqlite` feature, "sqlite" (tasks.db). Defaults to what --file's extension implies,
+    /// else json
+    #[arg(long, global = true, value_enum)]
+    store_format: Option<StoreFormat>,
+    /// Task file to use instead of tasks.json/tasks.jsonl. "-" reads the list from stdin
+    /// and writes it to stdout after a change (success messages are suppressed)
+    #[arg(long, global = true, value_name = "PATH")]
+    file: Option<PathBuf>,
+    /// Validate and report changes without saving them
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// When to color output: auto (only on a terminal without NO_COLOR set), always
+    /// or never
+    #[arg(long, global = true, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Same as --color=never
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Show dates in task lists relative to now, e.g. "3 days ago" or "in 2 hours"
+    #[arg(long, global = true)]
+    relative: bool,
+    /// Show dates in this IANA time zone (e.g. America/New_York) instead of local time
+    #[arg(long, global = true, value_name = "ZONE")]
+    tz: Option<String>,
+    /// Print errors to stderr as JSON objects, `{"error": "..."}`; implied by `--json`
+    #[arg(long, global = true)]
+    json_errors: bool,
+    /// Enter and show dates in this strftime-style format, e.g. "%d/%m/%Y %H:%M";
+    /// overrides `date_format` in the config file
+    #[arg(long, global = true, value_name = "FORMAT")]
+    date_format: Option<String>,
+    /// Cut descriptions in task lists to at most N characters, ending with "…"
+    #[arg(long, global = true, value_name = "N")]
+    truncate: Option<usize>,
+    /// Print task list columns joined by this string (`\t` for a tab) instead of the
+    /// usual layout: title, description, status, category, date, due, assignee,
+    /// label, subtasks done/total and "blocked", leaving empty columns empty
+    #[arg(long, global = true, value_name = "STR", value_parser = parse_separator)]
+    separator: Option<String>,
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Add a new task
+    #[command(visible_alias = "a")]
+    Add {
+        /// Taken from the template if --template is given
+        #[arg(required_unless_present_any = ["template", "from_file"])]
+        title: Option<String>,
+        /// Taken from the template if --template is given
+        #[arg(required_unless_present_any = ["template", "from_file"])]
+        description: Option<String>,
+        /// Creation date; defaults to now if --template or --from-file is given
+        #[arg(value_parser = parse_date, required_unless_present_any = ["template", "from_file"])]
+        date: Option<DateTime<Utc>>,
+        /// Defaults to the template's category, then `default_category` from the config
+        /// file, or "general"
+        category: Option<String>,
+        /// Start from a template saved with `template save`; other arguments override it
+        #[arg(long, value_name = "NAME")]
+        template: Option<String>,
+        /// Add a task per line of this file: `title`, optionally followed by
+        /// `| description` and `| category`. Other options apply to every task
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["title", "template", "overwrite"])]
+        from_file: Option<PathBuf>,
+        /// Titles of tasks that must be done before this one (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        depends_on: Vec<String>,
+        /// Due date (YYYY-MM-DD HH:MM)
+        #[arg(long, value_parser = parse_date)]
+        due: Option<DateTime<Utc>>,
+        /// Color label: black, red, green, yellow, blue, magenta, cyan, white or #rrggbb
+        #[arg(long, value_parser = parse_label)]
+        label: Option<String>,
+        /// low, medium or high; defaults to the category's default from the config, or medium
+        #[arg(long)]
+        priority: Option<Priority>,
+        /// Who the task is assigned to
+        #[arg(long)]
+        assignee: Option<String>,
+        /// Who is adding the task; defaults to the current OS user
+        #[arg(long, value_name = "USER")]
+        created_by: Option<String>,
+        /// Estimated effort in minutes
+        #[arg(long, value_name = "MINUTES")]
+        estimate: Option<u32>,
+        /// Repeat from the due date: daily, weekly, monthly, yearly or e.g. 2w, 3mo
+        #[arg(long, value_name = "RULE", requires = "due")]
+        recur: Option<Recurrence>,
+        /// Replace an existing task with the same title instead of failing
+        #[arg(long)]
+        overwrite: bool,
+        /// When overwriting, keep the replaced task's creation date
+        #[arg(long, requires = "overwrite")]
+        keep_date: bool,
+        /// Don't warn about existing tasks that look like duplicates of this one
+        #[arg(long)]
+        no_dup_check: bool,
+        /// Keep the description in its own file next to the list, for long texts;
+        /// the list keeps only its first line
+        #[arg(long, conflicts_with = "from_file")]
+        external_description: bool,
+    },
+    /// Mark a task as done
+    #[command(visible_alias = "do")]
+    Done {
+        /// One or more titles (or unique title prefixes); omit to pick one interactively
+        #[arg(value_name = "TITLE")]
+        titles: Vec<String>,
+        /// Mark every task matching this predicate as done
+        #[arg(long = "where", value_name = "PREDICATE", conflicts_with = "titles")]
+        predicate: Option<String>,
+        /// Don't ask for confirmation before marking tasks done with --where
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Move a done task back to active
+    Reopen { title: String },
+    /// Mark a task as just modified, e.g. after reviewing it, without changing it
+    Touch {
+        title: String,
+        /// Also reset the creation date to now, so the task sorts as the newest one
+        #[arg(long)]
+        restamp: bool,
+    },
+    /// Update an existing task (interactively, unless a field option is given)
+    Update {
+        title: String,
+        /// Color label: black, red, green, yellow, blue, magenta, cyan, white or #rrggbb
+        #[arg(long, value_parser = parse_label)]
+        label: Option<String>,
+        /// low, medium or high
+        #[arg(long)]
+        priority: Option<Priority>,
+        /// Who the task is assigned to
+        #[arg(long)]
+        assignee: Option<String>,
+        /// Add a line to the end of the description instead of replacing it
+        #[arg(long, value_name = "TEXT")]
+        append_description: Option<String>,
+        /// Save the answers to the interactive prompts without asking first
+        #[arg(long)]
+        no_confirm: bool,
+    },
+    /// Edit a task as JSON in $EDITOR
+    Edit { title: String },
+    /// Delete a task
+    #[command(visible_alias = "rm")]
+    Delete {
+        /// One or more titles (or unique title prefixes); omit to pick one interactively
+        #[arg(value_name = "TITLE")]
+        titles: Vec<String>,
+        /// Delete every task matching this predicate
+        #[arg(long = "where", value_name = "PREDICATE", conflicts_with = "titles")]
+        predicate: Option<String>,
+        /// Don't ask for confirmation before deleting with --where
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Copy the task file to a timestamped backup
+    Backup {
+        /// Backup file, or a directory to put the timestamped backup in
+        dest: Option<PathBuf>,
+    },
+    /// Move all done tasks into the archive file next to the list
+    Archive,
+    /// Permanently delete archived tasks
+    Purge {
+        /// Only purge tasks completed before this: a duration (e.g. 30d) or a date (YYYY-MM-DD HH:MM)
+        #[arg(long, value_name = "DURATION|DATE", value_parser = parse_cutoff)]
+        older_than: Option<DateTime<Utc>>,
+        /// Confirm the purge; it cannot be undone
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Move a task to another list file
+    #[command(visible_alias = "mv")]
+    Move {
+        title: String,
+        dest: PathBuf,
+        /// What to do if the destination already has a task with this title
+        #[arg(long, value_enum, default_value_t = ConflictStrategy::Fail)]
+        on_conflict: ConflictStrategy,
+    },
+    /// Append a progress note to a task
+    Note { title: String, text: String },
+    /// Link a file or URL to a task
+    Attach {
+        title: String,
+        /// A local path (warned about if it doesn't exist) or a URL
+        path: String,
+    },
+    /// Add or check off checklist items within a task
+    Subtask {
+        #[command(subcommand)]
+        action: SubtaskAction,
+    },
+    /// Make a task repeat, or preview when it next comes due
+    Recur {
+        #[command(subcommand)]
+        action: RecurAction,
+    },
+    /// Show all details of a task, including its notes
+    Show { title: String },
+    /// Record time spent on a task
+    Log { title: String, minutes: u32 },
+    /// Print estimated vs spent time across active tasks
+    Stats {
+        /// Instead, count tasks created and completed since then: a duration (e.g. 7d)
+        /// or a date (YYYY-MM-DD HH:MM)
+        #[arg(long, value_name = "DURATION|DATE", value_parser = parse_cutoff)]
+        since: Option<DateTime<Utc>>,
+    },
+    /// List the categories in use, with how many tasks each has
+    Categories,
+    /// Move every task in one category to another
+    #[command(visible_alias = "move-to-category")]
+    Recategorize { from: String, to: String },
+    /// Check the task list for problems; exits with an error if any are serious
+    Check,
+    /// Merge tasks whose titles differ only in case or whitespace into the oldest one
+    Dedupe {
+        /// Don't ask for confirmation before merging
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Fill the list with sample tasks for demos and manual testing
+    #[command(hide = true)]
+    Seed {
+        count: usize,
+        /// Add the samples even if the list already has tasks
+        #[arg(long)]
+        force: bool,
+    },
+    /// Set a reminder for a task
+    Remind {
+        title: String,
+        /// Reminder time (YYYY-MM-DD HH:MM)
+        #[arg(value_parser = parse_date)]
+        at: DateTime<Utc>,
+    },
+    /// Print active tasks whose reminder time has passed
+    CheckReminders {
+        /// Also show a desktop notification for each reminder (requires the `notify` feature)
+        #[arg(long)]
+        notify: bool,
+    },
+    /// Browse and edit tasks in a full-screen interface
+    #[cfg(feature = "tui")]
+    Tui,
+    /// Select tasks based on a predicate
+    Select {
+        #[arg(required_unless_present = "saved")]
+        predicate: Option<String>,
+        /// Use a predicate saved with `query save` instead
+        #[arg(long, value_name = "NAME", conflicts_with = "predicate")]
+        saved: Option<String>,
+        #[command(flatten)]
+        age: AgeFilter,
+        /// Print tab-separated columns with a header row
+        #[arg(long)]
+        tsv: bool,
+        /// Print the matching tasks as a JSON array
+        #[arg(long, conflicts_with = "tsv")]
+        json: bool,
+        /// Print only how many tasks matched
+        #[arg(long, conflicts_with_all = ["tsv", "json"])]
+        count_only: bool,
+        /// Exit with status 2 if no tasks match
+        #[arg(long)]
+        fail_on_empty: bool,
+        /// Write the results to this file instead of stdout
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Save task templates to add from later with `add --template`
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Save named predicates and run them later
+    Query {
+        #[command(subcommand)]
+        action: QueryAction,
+    },
+    /// Print the number of tasks, optionally only those matching a predicate
+    Count { predicate: Option<String> },
+    /// List all tasks
+    #[command(visible_alias = "ls")]
+    List {
+        /// Output template, e.g. "{title} [{status}]". Placeholders: {title}, {description},
+        /// {status}, {category}, {date}, {due}
+        #[arg(long, value_name = "TEMPLATE", conflicts_with = "tsv")]
+        format: Option<String>,
+        #[command(flatten)]
+        age: AgeFilter,
+        #[command(flatten)]
+        status: StatusFilter,
+        /// Print tasks under a header per category or status, with counts
+        #[arg(long, value_name = "FIELD", conflicts_with_all = ["tsv", "format"])]
+        group_by: Option<GroupBy>,
+        /// Print tab-separated columns with a header row
+        #[arg(long)]
+        tsv: bool,
+        /// Print the tasks as a JSON array
+        #[arg(long, conflicts_with_all = ["tsv", "format", "group_by"])]
+        json: bool,
+        /// Print only titles, each terminated by a NUL byte (for `xargs -0`)
+        #[arg(long, conflicts_with_all = ["tsv", "json", "format", "group_by"])]
+        print0: bool,
+        /// Print a self-contained HTML page with a table per category, for sharing
+        #[arg(
+            long,
+            conflicts_with_all = ["tsv", "json", "format", "group_by", "print0", "columns", "table"]
+        )]
+        html: bool,
+        /// Print only these fields, in this order, e.g. `title,status,date`; joined by
+        /// --separator, or a tab without one
+        #[arg(
+            long,
+            value_enum,
+            value_name = "FIELDS",
+            value_delimiter = ',',
+            conflicts_with_all = ["tsv", "json", "format", "print0"]
+        )]
+        columns: Vec<Column>,
+        /// Print an aligned table with column headers, fitted to the terminal's width
+        /// by truncating descriptions
+        #[arg(
+            long,
+            conflicts_with_all = ["tsv", "json", "format", "print0", "group_by", "separator"]
+        )]
+        table: bool,
+        /// Draw the table with ASCII characters instead of box-drawing ones
+        #[arg(long, requires = "table")]
+        ascii: bool,
+        /// Order tasks by these keys, e.g. `status,due`; ties are broken by creation
+        /// date, then title
+        #[arg(long, value_enum, value_name = "KEYS", value_delimiter = ',')]
+        sort: Vec<SortKey>,
+        /// Put done tasks after all active ones, whatever the sort order
+        #[arg(long)]
+        done_last: bool,
+        /// Write the list to this file instead of stdout
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Re-print the list whenever the task file changes
+        #[cfg(feature = "watch")]
+        #[arg(long, conflicts_with = "output")]
+        watch: bool,
+    },
+    /// List active tasks that are past their due date
+    Overdue,
+    /// List active tasks due today
+    Today,
+    /// Show the single most important active, unblocked task
+    Next,
+    /// List tasks added or changed after the given time, oldest change first
+    Changes {
+        /// YYYY-MM-DD HH:MM
+        #[arg(value_parser = parse_date)]
+        since: DateTime<Utc>,
+    },
+}
+
+impl Commands {
+    /// Whether the command was asked for JSON output, so errors should be JSON too.
+    fn json_output(&self) -> bool {
         match self {
-            TaskStatus::Active => write!(f, "on"),
-            TaskStatus::Done => write!(f, "done"),
+            Commands::List { json, .. } | Commands::Select { json, .. } => *json,
+            _ => false,
         }
     }
 }
 
-impl FromStr for TaskStatus {
-    type Err = String;
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// Save a template under a name, replacing any template with the same name
+    Save {
+        name: String,
+        title: String,
+        description: String,
+        category: Option<String>,
+        /// low, medium or high
+        #[arg(long)]
+        priority: Option<Priority>,
+    },
+    /// List the saved templates
+    List,
+}
+
+#[derive(Subcommand)]
+enum QueryAction {
+    /// Save a predicate under a name, replacing any query with the same name
+    Save { name: String, predicate: String },
+    /// List the tasks matching a saved query
+    Run { name: String },
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "on" | "active" | "a" => Ok(TaskStatus::Active),
-            "done" | "d" => Ok(TaskStatus::Done),
-            _ => Err(format!("Invalid status: {}", s)),
+#[derive(Subcommand)]
+enum RecurAction {
+    /// Set how a task repeats: daily, weekly, monthly, yearly or e.g. 2w, 3mo
+    Set { title: String, rule: Recurrence },
+    /// Stop a task from repeating
+    Clear { title: String },
+    /// Print the next due dates of a recurring task
+    #[command(visible_alias = "list")]
+    Preview {
+        title: String,
+        #[arg(default_value_t = 5)]
+        n: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum SubtaskAction {
+    /// Append a subtask to a task
+    Add { title: String, text: String },
+    /// Toggle a subtask between done and not done
+    Done {
+        title: String,
+        /// 1-based position, as shown by `show`
+        index: usize,
+    },
+}
+
+fn print_tasks(todo_list: &TodoList, tasks: Vec<&Task>, reporter: &Reporter) {
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    write_tasks(&mut out, todo_list, tasks, reporter)
+        .and_then(|()| out.flush())
+        .expect("Failed to write to stdout");
+}
+
+/// A task field that `list --columns` and `--separator` can print.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+enum Column {
+    Title,
+    Description,
+    Status,
+    Category,
+    /// Creation date
+    Date,
+    Due,
+    Assignee,
+    Label,
+    /// Subtasks done/total
+    Subtasks,
+    /// "blocked" if the task waits on an unfinished dependency
+    Blocked,
+}
+
+impl Column {
+    /// Every column, in the order `--separator` prints them without `--columns`.
+    const ALL: [Column; 10] = [
+        Column::Title,
+        Column::Description,
+        Column::Status,
+        Column::Category,
+        Column::Date,
+        Column::Due,
+        Column::Assignee,
+        Column::Label,
+        Column::Subtasks,
+        Column::Blocked,
+    ];
+
+    /// What `list --table` prints without `--columns`.
+    const TABLE: [Column; 6] = [
+        Column::Title,
+        Column::Description,
+        Column::Status,
+        Column::Category,
+        Column::Date,
+        Column::Due,
+    ];
+
+    fn header(self) -> &'static str {
+        match self {
+            Column::Title => "Title",
+            Column::Description => "Description",
+            Column::Status => "Status",
+            Column::Category => "Category",
+            Column::Date => "Date",
+            Column::Due => "Due",
+            Column::Assignee => "Assignee",
+            Column::Label => "Label",
+            Column::Subtasks => "Subtasks",
+            Column::Blocked => "Blocked",
+        }
+    }
+
+    /// The column's value for `task`, or an empty string if it has none.
+    fn value(self, todo_list: &TodoList, task: &Task, reporter: &Reporter) -> String {
+        match self {
+            Column::Title => task.title.clone(),
+            Column::Description => reporter.description(&task.description),
+            Column::Status => task.status.to_string(),
+            Column::Category => task.category.to_string(),
+            Column::Date => reporter.date(task.creation_date),
+            Column::Due => task.due.map(|due| reporter.date(due)).unwrap_or_default(),
+            Column::Assignee => task.assignee.clone().unwrap_or_default(),
+            Column::Label => task.label.clone().unwrap_or_default(),
+            Column::Subtasks => match task.subtask_counts() {
+                (_, 0) => String::new(),
+                (done, total) => format!("{}/{}", done, total),
+            },
+            Column::Blocked => {
+                let blocked = todo_list.is_blocked(task) && task.status == TaskStatus::Active;
+                if blocked { "blocked" } else { "" }.to_string()
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct Category(String);
+/// Writes tasks as a table of `reporter.columns` (`Column::TABLE` if none were
+/// chosen). Descriptions are truncated to fit `max_width`.
+fn write_table(
+    out: &mut dyn Write,
+    todo_list: &TodoList,
+    tasks: Vec<&Task>,
+    reporter: &Reporter,
+    style: TableStyle,
+    max_width: Option<usize>,
+) -> std::io::Result<()> {
+    let columns = match reporter.columns.as_slice() {
+        [] => &Column::TABLE[..],
+        columns => columns,
+    };
+    let headers: Vec<&str> = columns.iter().map(|column| column.header()).collect();
+    let rows: Vec<Vec<String>> = tasks
+        .into_iter()
+        .map(|task| {
+            columns
+                .iter()
+                .map(|column| column.value(todo_list, task, reporter))
+                .collect()
+        })
+        .collect();
+    let shrink = columns.iter().position(|&c| c == Column::Description);
+    write!(
+        out,
+        "{}",
+        render_table(&headers, &rows, style, max_width, shrink)
+    )
+}
+
+fn write_tasks(
+    out: &mut dyn Write,
+    todo_list: &TodoList,
+    tasks: Vec<&Task>,
+    reporter: &Reporter,
+) -> std::io::Result<()> {
+    for task in tasks {
+        if let Some((columns, separator)) = reporter.column_layout() {
+            let values: Vec<String> = columns
+                .iter()
+                .map(|column| column.value(todo_list, task, reporter))
+                .collect();
+            writeln!(out, "{}", values.join(separator))?;
+            continue;
+        }
+        let title = match &task.label {
+            Some(label) if reporter.color => colorize(&task.title, label),
+            Some(label) => format!("[{}] {}", label, task.title),
+            None => task.title.clone(),
+        };
+        let blocked = if todo_list.is_blocked(task) && task.status == TaskStatus::Active {
+            " [blocked]"
+        } else {
+            ""
+        };
+        let due = match task.due {
+            Some(due) => format!(" - due {}", reporter.date(due)),
+            None => String::new(),
+        };
+        let assignee = match &task.assignee {
+            Some(assignee) => format!(" @{}", assignee),
+            None => String::new(),
+        };
+        let subtasks = match task.subtask_counts() {
+            (_, 0) => String::new(),
+            (done, total) => format!(" [{}/{}]", done, total),
+        };
+        writeln!(
+            out,
+            "{}: {} ({}) - {} - {}{}{}{}{}",
+            title,
+            reporter.description(&task.description),
+            task.status,
+            task.category,
+            reporter.date(task.creation_date),
+            due,
+            assignee,
+            subtasks,
+            blocked
+        )?;
+    }
+    Ok(())
+}
 
-impl std::fmt::Display for Category {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+/// Opens `path` for writing a command's results, creating missing parent directories,
+/// or stdout if no path was given. Either way output is buffered, so callers must flush.
+fn open_output(path: Option<&Path>) -> Result<Box<dyn Write>, String> {
+    let Some(path) = path else {
+        return Ok(Box::new(std::io::BufWriter::new(std::io::stdout().lock())));
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
     }
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    Ok(Box::new(std::io::BufWriter::new(file)))
 }
 
-impl FromStr for Category {
-    type Err = String;
+fn output_error(e: std::io::Error) -> String {
+    format!("Failed to write output: {}", e)
+}
+
+#[derive(Args)]
+struct AgeFilter {
+    /// Only tasks created more than this long ago (e.g. 30m, 24h, 7d, 2w)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    older_than: Option<Duration>,
+    /// Only tasks created less than this long ago (e.g. 30m, 24h, 7d, 2w)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    newer_than: Option<Duration>,
+    /// Only tasks created after this date, or within this duration (e.g. 7d)
+    #[arg(long, value_name = "WHEN", value_parser = parse_cutoff)]
+    created_after: Option<DateTime<Utc>>,
+    /// Only tasks added or changed after this date, or within this duration (e.g. 7d)
+    #[arg(long, value_name = "WHEN", value_parser = parse_cutoff)]
+    modified_after: Option<DateTime<Utc>>,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Category(s.to_string()))
+impl AgeFilter {
+    fn apply<'a>(&self, tasks: Vec<&'a Task>) -> Vec<&'a Task> {
+        let now = Utc::now();
+        tasks
+            .into_iter()
+            .filter(|task| {
+                self.older_than
+                    .is_none_or(|age| task.creation_date < now - age)
+                    && self
+                        .newer_than
+                        .is_none_or(|age| task.creation_date > now - age)
+                    && self
+                        .created_after
+                        .is_none_or(|cutoff| task.creation_date > cutoff)
+                    && self
+                        .modified_after
+                        .is_none_or(|cutoff| task.modified_at() > cutoff)
+            })
+            .collect()
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Task {
-    pub title: String,
-    pub description: String,
-    pub creation_date: DateTime<Local>,
-    pub category: Category,
-    pub status: TaskStatus,
+#[derive(Args)]
+struct StatusFilter {
+    /// Only active tasks
+    #[arg(long, conflicts_with = "done_only")]
+    active_only: bool,
+    /// Only done tasks
+    #[arg(long)]
+    done_only: bool,
+    /// Only active tasks whose due date has passed
+    #[arg(long, conflicts_with = "done_only")]
+    overdue_only: bool,
 }
 
-impl Task {
-    pub fn new(title: String, description: String, category: Category) -> Self {
-        Task {
-            title,
-            description,
-            creation_date: Local::now(),
-            category,
-            status: TaskStatus::Active,
+impl StatusFilter {
+    /// The predicates the flags stand for: `status = "on"`, `status = "done"`, or
+    /// for `--overdue-only` active and due before now.
+    fn predicates(&self) -> Vec<Predicate> {
+        let mut predicates = Vec::new();
+        if self.active_only || self.overdue_only {
+            predicates.push(Predicate::Status(TaskStatus::Active));
         }
+        if self.done_only {
+            predicates.push(Predicate::Status(TaskStatus::Done));
+        }
+        if self.overdue_only {
+            predicates.push(Predicate::DueBefore(Utc::now()));
+        }
+        predicates
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TodoList {
-    tasks: HashMap<String, Task>,
-    file_path: PathBuf,
+/// Global output settings threaded through command dispatch.
+#[derive(Clone)]
+struct Reporter {
+    quiet: bool,
+    dry_run: bool,
+    color: bool,
+    /// Whether to color output written to a file with `--output`, which only
+    /// `--color always` does.
+    color_files: bool,
+    relative: bool,
+    truncate: Option<usize>,
+    separator: Option<String>,
+    json_errors: bool,
+    /// Fields chosen with `list --columns`; empty for the usual layout.
+    columns: Vec<Column>,
 }
 
-impl TodoList {
-    pub fn new(file_path: PathBuf) -> Self {
-        let tasks = if file_path.exists() {
-            let content = fs::read_to_string(&file_path).expect("Failed to read file");
-            serde_json::from_str(&content).unwrap_or_else(|_| HashMap::new())
+impl Reporter {
+    /// Prints a success confirmation unless `--quiet` was given, marking it when
+    /// nothing was actually saved.
+    fn confirm(&self, message: String) {
+        if self.quiet {
+            return;
+        }
+        if self.dry_run {
+            println!("{} (dry run)", message);
         } else {
-            HashMap::new()
-        };
-        TodoList { tasks, file_path }
+            println!("{}", message);
+        }
     }
 
-    pub fn add_task(&mut self, task: Task) -> Result<(), String> {
-        if self.tasks.contains_key(&task.title) {
-            Err(format!("Task with title '{}' already exists", task.title))
+    fn date(&self, date: DateTime<Utc>) -> String {
+        if self.relative {
+            humanize(date - Utc::now())
         } else {
-            self.tasks.insert(task.title.clone(), task);
-            self.save();
-            Ok(())
+            format_date(date)
         }
     }
 
-    pub fn mark_as_done(&mut self, title: &str) -> Result<(), String> {
-        if let Some(task) = self.tasks.get_mut(title) {
-            task.status = TaskStatus::Done;
-            self.save();
-            Ok(())
-        } else {
-            Err(format!("Task with title '{}' not found", title))
+    /// The settings to use when writing to `--output`, or these if there is none.
+    fn for_output(&self, output: Option<&Path>) -> Reporter {
+        Reporter {
+            color: if output.is_some() {
+                self.color_files
+            } else {
+                self.color
+            },
+            ..self.clone()
         }
     }
 
-    pub fn update_task(&mut self, title: &str, new_task: Task) -> Result<(), String> {
-        if let Some(task) = self.tasks.get_mut(title) {
-            *task = new_task;
-            self.save();
-            Ok(())
-        } else {
-            Err(format!("Task with title '{}' not found", title))
+    /// The columns and separator to print tasks with, or `None` for the usual layout.
+    fn column_layout(&self) -> Option<(&[Column], &str)> {
+        match (self.columns.as_slice(), self.separator.as_deref()) {
+            ([], None) => None,
+            ([], Some(separator)) => Some((&Column::ALL, separator)),
+            (columns, separator) => Some((columns, separator.unwrap_or("\t"))),
         }
     }
 
-    pub fn delete_task(&mut self, title: &str) -> Result<(), String> {
-        if self.tasks.remove(title).is_some() {
-            self.save();
-            Ok(())
+    fn description(&self, description: &str) -> String {
+        match self.truncate {
+            Some(n) => truncate_str(description, n),
+            None => description.to_string(),
+        }
+    }
+}
+
+fn print_task_details(todo_list: &TodoList, task: &Task) {
+    println!("Title:       {}", task.title);
+    match todo_list.full_description(task) {
+        Ok(description) => println!("Description: {}", description),
+        Err(e) => println!(
+            "Description: {} (full text unavailable: {})",
+            task.description, e
+        ),
+    }
+    println!("Status:      {}", task.status);
+    println!("Category:    {}", task.category);
+    println!("Priority:    {}", task.priority);
+    if let Some(assignee) = &task.assignee {
+        println!("Assignee:    {}", assignee);
+    }
+    if let Some(label) = &task.label {
+        println!("Label:       {}", label);
+    }
+    println!("Created:     {}", format_date(task.creation_date));
+    if !task.created_by.is_empty() {
+        println!("Created by:  {}", task.created_by);
+    }
+    if task.estimate_minutes.is_some() || task.spent_minutes.is_some() {
+        let estimate = task
+            .estimate_minutes
+            .map_or("-".to_string(), |minutes| format!("{} min", minutes));
+        println!(
+            "Effort:      {} min spent of {}",
+            task.spent_minutes.unwrap_or(0),
+            estimate
+        );
+    }
+    if let Some(due) = task.due {
+        println!("Due:         {}", format_date(due));
+    }
+    if let Some(recurrence) = task.recurrence {
+        println!("Repeats:     {}", recurrence);
+    }
+    if !task.depends_on.is_empty() {
+        let blocked = if todo_list.is_blocked(task) {
+            " [blocked]"
         } else {
-            Err(format!("Task with title '{}' not found", title))
+            ""
+        };
+        println!("Depends on:  {}{}", task.depends_on.join(", "), blocked);
+    }
+    if let Some(percent) = task.progress_percent() {
+        let (done, total) = task.subtask_counts();
+        println!("Subtasks:    [{}/{}] {}%", done, total, percent);
+        for (i, subtask) in task.subtasks.iter().enumerate() {
+            let mark = if subtask.done { "x" } else { " " };
+            println!("  {}. [{}] {}", i + 1, mark, subtask.text);
+        }
+    }
+    if !task.attachments.is_empty() {
+        println!("Attachments:");
+        for attachment in &task.attachments {
+            println!("  {}", attachment);
+        }
+    }
+    if !task.notes.is_empty() {
+        let mut notes: Vec<_> = task.notes.iter().collect();
+        notes.sort_by_key(|note| note.created_at);
+        println!("Notes:");
+        for note in notes {
+            println!("  [{}] {}", format_date(note.created_at), note.text);
         }
     }
+}
 
-    pub fn get_all_tasks(&self) -> Vec<&Task> {
-        self.tasks.values().collect()
+#[cfg(feature = "notify")]
+fn send_notifications(tasks: &[&Task]) -> Result<(), String> {
+    for task in tasks {
+        notify_rust::Notification::new()
+            .summary(&task.title)
+            .body(&task.description)
+            .show()
+            .map_err(|e| format!("Failed to show notification: {}", e))?;
     }
+    Ok(())
+}
+
+#[cfg(not(feature = "notify"))]
+fn send_notifications(_tasks: &[&Task]) -> Result<(), String> {
+    Err("Desktop notifications require building with `--features notify`".to_string())
+}
+
+/// Lets the user choose one of `tasks` by typing to filter their titles.
+#[cfg(feature = "pick")]
+fn pick_task(tasks: Vec<&Task>, prompt: &str) -> Result<String, String> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Err("No title given, and not running in a terminal to pick one".to_string());
+    }
+    if tasks.is_empty() {
+        return Err("No tasks to pick from".to_string());
+    }
+    let mut titles: Vec<&str> = tasks.iter().map(|task| task.title.as_str()).collect();
+    titles.sort_unstable();
+    let choice = dialoguer::FuzzySelect::new()
+        .with_prompt(prompt)
+        .items(&titles)
+        .interact_opt()
+        .map_err(|e| format!("Failed to pick a task: {}", e))?;
+    choice
+        .map(|i| titles[i].to_string())
+        .ok_or_else(|| "No task picked".to_string())
+}
+
+#[cfg(not(feature = "pick"))]
+fn pick_task(_tasks: Vec<&Task>, _prompt: &str) -> Result<String, String> {
+    Err(
+        "No title given; picking a task interactively requires building with `--features pick`"
+            .to_string(),
+    )
+}
 
-    pub fn filter_tasks(&self, predicate: &str) -> Result<Vec<&Task>, String> {
-        let predicates = parse_predicates(predicate)?;
-        Ok(self
-            .tasks
-            .values()
-            .filter(|task| predicates.iter().all(|p| p.matches(task)))
-            .collect())
+/// Interactively asks for each field, keeping the current value on empty input.
+fn prompt_task_update(old_task: &Task) -> Task {
+    println!("Updating task: {}", old_task.title);
+
+    println!("Enter new description (press Enter to keep current):");
+    let mut new_description = String::new();
+    std::io::stdin().read_line(&mut new_description).unwrap();
+    let new_description = new_description.trim();
+    let new_description = if new_description.is_empty() {
+        old_task.description.clone()
+    } else {
+        new_description.to_string()
+    };
+
+    println!("Enter new date (YYYY-MM-DD HH:MM) (press Enter to keep current):");
+    let mut new_date = String::new();
+    std::io::stdin().read_line(&mut new_date).unwrap();
+    let new_date = new_date.trim();
+    let new_date = if new_date.is_empty() {
+        old_task.creation_date
+    } else {
+        parse_date(new_date).unwrap_or(old_task.creation_date)
+    };
+
+    println!("Enter new due date (YYYY-MM-DD HH:MM) (press Enter to keep current):");
+    let mut new_due = String::new();
+    std::io::stdin().read_line(&mut new_due).unwrap();
+    let new_due = new_due.trim();
+    let new_due = if new_due.is_empty() {
+        old_task.due
+    } else {
+        parse_date(new_due).ok().or(old_task.due)
+    };
+
+    println!("Enter new category (press Enter to keep current):");
+    let mut new_category = String::new();
+    std::io::stdin().read_line(&mut new_category).unwrap();
+    let new_category = new_category.trim();
+    let new_category = if new_category.is_empty() {
+        old_task.category.clone()
+    } else {
+        Category(new_category.to_string())
+    };
+
+    println!("Enter new status (on/done) (press Enter to keep current):");
+    let mut new_status = String::new();
+    std::io::stdin().read_line(&mut new_status).unwrap();
+    let new_status = new_status.trim();
+    let new_status = if new_status.is_empty() {
+        old_task.status.clone()
+    } else {
+        new_status.parse().unwrap_or(old_task.status.clone())
+    };
+
+    Task {
+        title: old_task.title.clone(),
+        description: new_description,
+        creation_date: new_date,
+        category: new_category,
+        status: new_status,
+        due: new_due,
+        ..old_task.clone()
     }
+}
 
-    fn save(&self) {
-        let content = serde_json::to_string(&self.tasks).expect("Failed to serialize tasks");
-        let tmp_path = self.file_path.with_extension("tmp");
-        fs::write(&tmp_path, content).expect("Failed to write to temp file");
-        fs::rename(&tmp_path, &self.file_path).expect("Failed to rename temp file");
+/// Prints the task as it would be saved and asks whether to save it, or just
+/// returns true if `ask` is false.
+fn confirm_update(todo_list: &TodoList, new_task: &Task, ask: bool) -> bool {
+    if !ask {
+        return true;
     }
+    println!();
+    print_task_details(todo_list, new_task);
+    prompt_yes_no("Save changes? [y/N]")
 }
 
-#[derive(Debug, PartialEq)]
-enum Predicate {
-    Category(String),
-    Status(TaskStatus),
-    DateBefore(DateTime<Local>),
-    DateAfter(DateTime<Local>),
-    DescriptionContains(String),
+/// Warns about existing tasks that look like duplicates of `task`. At a terminal the
+/// user is asked whether to add it anyway; otherwise it is added after the warning.
+fn confirm_not_duplicate(todo_list: &TodoList, task: &Task) -> bool {
+    let similar = todo_list.find_similar(task);
+    if similar.is_empty() {
+        return true;
+    }
+    let titles: Vec<String> = similar.iter().map(|t| format!("'{}'", t.title)).collect();
+    eprintln!(
+        "Warning: '{}' looks similar to {}",
+        task.title,
+        titles.join(", ")
+    );
+    !std::io::stdin().is_terminal() || prompt_yes_no("Add it anyway? [y/N]")
 }
 
-impl Predicate {
-    fn matches(&self, task: &Task) -> bool {
-        match self {
-            Predicate::Category(category) => &task.category.0 == category,
-            Predicate::Status(status) => &task.status == status,
-            Predicate::DateBefore(date) => task.creation_date < *date,
-            Predicate::DateAfter(date) => task.creation_date > *date,
-            Predicate::DescriptionContains(text) => task.description.contains(text),
+/// Writes the task to a temp file as JSON, opens it in `$EDITOR` (or `vi`) and
+/// parses the result back. The temp file is removed either way.
+fn edit_in_editor(task: &Task) -> Result<Task, String> {
+    let path = std::env::temp_dir().join(format!("todo-edit-{}.json", std::process::id()));
+    let content = serde_json::to_string_pretty(task).expect("Failed to serialize task");
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write temp file: {}", e))?;
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status();
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            return Err(format!(
+                "Editor exited with {}; task left unchanged",
+                status
+            ))
         }
+        Err(e) => return Err(format!("Failed to launch editor '{}': {}", program, e)),
     }
+    let edited = edited.map_err(|e| format!("Failed to read edited task: {}", e))?;
+    serde_json::from_str(&edited)
+        .map_err(|e| format!("Invalid task JSON, task left unchanged: {}", e))
 }
 
-impl FromStr for Predicate {
-    type Err = String;
+/// Calls `render` once, then again after every change to `path` until interrupted.
+#[cfg(feature = "watch")]
+fn watch_file(path: &std::path::Path, mut render: impl FnMut()) -> Result<(), String> {
+    use notify::{RecursiveMode, Watcher};
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("Failed to start watcher: {}", e))?;
+    // Watch the directory, not the file: saves replace the file by renaming a temp
+    // file over it, so a watch on the file itself would stay on the old inode.
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => std::path::Path::new("."),
+    };
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.splitn(3, ' ').collect();
-        if parts.len() < 3 {
-            return Err("Invalid predicate format".to_string());
+    render();
+    while let Ok(event) = rx.recv() {
+        let event = event.map_err(|e| format!("Watch error: {}", e))?;
+        // Reading the file during a render fires access events; only react to writes,
+        // creates (the rename) and removals.
+        if !event.kind.is_access()
+            && event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == path.file_name())
+        {
+            // A single save fires several events; let them settle and render once.
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            while rx.try_recv().is_ok() {}
+            render();
         }
+    }
+    Ok(())
+}
 
-        match parts[0] {
-            "category" => Ok(Predicate::Category(parts[2].to_string())),
-            "status" => Ok(Predicate::Status(parts[2].parse()?)),
-            "date" => {
-                let date = NaiveDateTime::parse_from_str(parts[2], "%Y-%m-%d %H:%M")
-                    .map_err(|e| e.to_string())?;
-                let date = Local.from_local_datetime(&date).unwrap();
-                match parts[1] {
-                    "<" => Ok(Predicate::DateBefore(date)),
-                    ">" => Ok(Predicate::DateAfter(date)),
-                    _ => Err("Invalid date comparison operator".to_string()),
-                }
-            }
-            "description" => {
-                if parts[1] != "like" {
-                    return Err("Invalid description predicate".to_string());
-                }
-                Ok(Predicate::DescriptionContains(
-                    parts[2].trim_matches('"').to_string(),
-                ))
+/// Confirms each successful title and prints each failure. With a single title its
+/// error is returned as-is; otherwise a summary error is returned if any failed.
+fn report_each(
+    reporter: &Reporter,
+    results: Vec<Result<String, String>>,
+    action: &str,
+) -> Result<(), String> {
+    if let [Err(e)] = results.as_slice() {
+        return Err(e.clone());
+    }
+    let total = results.len();
+    let mut failed = 0;
+    for result in results {
+        match result {
+            Ok(title) => reporter.confirm(format!("Task '{}' {}", title, action)),
+            Err(e) => {
+                print_error(&e, reporter.json_errors);
+                failed += 1;
             }
-            _ => Err(format!("Unknown predicate type: {}", parts[0])),
         }
     }
+    if failed > 0 {
+        Err(format!("{} of {} task(s) failed", failed, total))
+    } else {
+        Ok(())
+    }
 }
 
-#[derive(Parser)]
-#[command(name = "todo")]
-#[command(about = "A simple TODO list CLI application", long_about = None)]
-struct Cli {
-    #[command(subcommand)]
-    command: Commands,
+/// Prints an error to stderr, as `{"error": "..."}` if `json` is set so programs
+/// reading JSON output can parse it too.
+fn print_error(message: &str, json: bool) {
+    if json {
+        eprintln!("{}", serde_json::json!({ "error": message }));
+    } else {
+        eprintln!("Error: {}", message);
+    }
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// Add a new task
-    Add {
-        title: String,
-        description: String,
-        #[arg(value_parser = parse_date)]
-        date: DateTime<Local>,
-        category: String,
-    },
-    /// Mark a task as done
-    Done { title: String },
-    /// Update an existing task
-    Update { title: String },
-    /// Delete a task
-    Delete { title: String },
-    /// Select tasks based on a predicate
-    Select { predicate: String },
-    /// List all tasks
-    List,
+/// Lets `--separator '\t'` mean a tab, since typing a literal one is awkward.
+fn parse_separator(s: &str) -> Result<String, String> {
+    Ok(s.replace("\\t", "\t"))
 }
 
-fn parse_date(date_str: &str) -> Result<DateTime<Local>, chrono::ParseError> {
-    let naive = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M")?;
-    Ok(Local.from_local_datetime(&naive).unwrap())
+/// The OS user name from `$USER` (or `%USERNAME%`), falling back to `whoami`, or an
+/// empty string if none of these work.
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|user| !user.is_empty())
+        .or_else(|| {
+            let output = std::process::Command::new("whoami").output().ok()?;
+            let user = String::from_utf8(output.stdout).ok()?;
+            output.status.success().then(|| user.trim().to_string())
+        })
+        .unwrap_or_default()
 }
 
-fn parse_predicates(predicate: &str) -> Result<Vec<Predicate>, String> {
-    let re = Regex::new(r#"(\w+)\s*(=|<|>|like)\s*"([^"]*)""#).unwrap();
-    let captures: Vec<_> = re.captures_iter(predicate).collect();
+fn prompt_yes_no(question: &str) -> bool {
+    println!("{}", question);
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).unwrap();
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
 
-    if captures.is_empty() {
-        return Err("Invalid predicate format".to_string());
+/// Shows what a bulk operation is about to do, one sorted line per affected task
+/// under a count, and asks before going ahead unless `yes` is set. In dry-run mode the
+/// preview is printed without asking, since nothing will be saved. Returns whether
+/// to proceed, having printed "Aborted." if not.
+fn confirm_bulk(mut items: Vec<String>, action: &str, yes: bool, reporter: &Reporter) -> bool {
+    if yes && !reporter.dry_run {
+        return true;
+    }
+    items.sort();
+    let tense = if reporter.dry_run {
+        "would be"
+    } else {
+        "will be"
+    };
+    println!("{} task(s) {} {}:", items.len(), tense, action);
+    for item in &items {
+        println!("  {}", item);
+    }
+    if reporter.dry_run || prompt_yes_no("Proceed? [y/N]") {
+        return true;
     }
+    println!("Aborted.");
+    false
+}
 
-    captures
-        .into_iter()
-        .map(|cap| {
-            let field = cap[1].to_lowercase();
-            let operator = &cap[2];
-            let value = cap[3].to_string();
-
-            match (field.as_str(), operator) {
-                ("category", "=") => Ok(Predicate::Category(value)),
-                ("status", "=") => TaskStatus::from_str(&value)
-                    .map(Predicate::Status)
-                    .map_err(|e| e.to_string()),
-                ("date", "<") => parse_date(&value)
-                    .map(Predicate::DateBefore)
-                    .map_err(|e| e.to_string()),
-                ("date", ">") => parse_date(&value)
-                    .map(Predicate::DateAfter)
-                    .map_err(|e| e.to_string()),
-                ("description", "like") => Ok(Predicate::DescriptionContains(value)),
-                _ => Err(format!("Unknown predicate: {}", field)),
-            }
-        })
-        .collect()
+/// The start of `date` in the local time zone. Where a DST change skips midnight
+/// there is no such local time, so UTC midnight stands in for it.
+fn local_midnight(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    let local = Local.from_local_datetime(&midnight);
+    local
+        .earliest()
+        .map(|date| date.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
 }
 
-fn main() {
-    let cli = Cli::parse();
-    let mut todo_list = TodoList::new(PathBuf::from("tasks.json"));
+/// Exit code for `select --fail-on-empty` when nothing matched. Errors exit with 1.
+const EXIT_NO_MATCHES: u8 = 2;
+
+fn run(cli: Cli, config: &Config) -> Result<ExitCode, String> {
+    if let Some(name) = &cli.tz {
+        match parse_timezone(name) {
+            Ok(tz) => set_display_timezone(tz),
+            Err(e) => eprintln!("Warning: {}; showing local time instead", e),
+        }
+    }
+    let is_stdio = cli.file.as_deref() == Some(Path::new(STDIO_PATH));
+    let store_format = match (cli.store_format, &cli.file) {
+        (Some(format), _) => format,
+        (None, Some(path)) => StoreFormat::for_path(path)?.unwrap_or_default(),
+        (None, None) => StoreFormat::default(),
+    };
+    if is_stdio && store_format != StoreFormat::Json {
+        return Err("--file - only supports the json store format".to_string());
+    }
+    let color_enabled = |is_terminal: bool| {
+        !cli.no_color
+            && cli
+                .color
+                .enabled(is_terminal, std::env::var_os("NO_COLOR").is_some())
+    };
+    let reporter = Reporter {
+        quiet: cli.quiet || is_stdio,
+        dry_run: cli.dry_run,
+        color: color_enabled(std::io::stdout().is_terminal()),
+        color_files: color_enabled(false),
+        relative: cli.relative,
+        truncate: cli.truncate,
+        separator: cli.separator,
+        json_errors: cli.json_errors || cli.command.json_output(),
+        columns: Vec::new(),
+    };
+    let file_path = cli.file.unwrap_or_else(|| match store_format {
+        StoreFormat::Json => PathBuf::from("tasks.json"),
+        StoreFormat::Jsonl => PathBuf::from("tasks.jsonl"),
+        #[cfg(feature = "sqlite")]
+        StoreFormat::Sqlite => PathBuf::from("tasks.db"),
+    });
+    let mut todo_list = TodoList::with_format(file_path.clone(), store_format)?;
+    todo_list.set_dry_run(cli.dry_run);
+    todo_list.set_backups(config.backups);
+    if let Some(threshold) = config.duplicate_threshold {
+        todo_list.set_similarity_threshold(threshold);
+    }
 
     match cli.command {
         Commands::Add {
@@ -283,285 +1214,730 @@ fn main() {
             description,
             date,
             category,
+            template,
+            from_file,
+            depends_on,
+            due,
+            label,
+            priority,
+            assignee,
+            created_by,
+            estimate,
+            recur,
+            overwrite,
+            keep_date,
+            no_dup_check,
+            external_description,
         } => {
-            let task = Task {
-                title: title.clone(),
+            let template = match template {
+                Some(name) => TemplateStore::load(TemplateStore::path_for(&file_path))?
+                    .get(&name)?
+                    .clone(),
+                None => TaskTemplate::default(),
+            };
+            let fields = template.instantiate(TemplateOverrides {
+                title,
                 description,
-                creation_date: date,
-                category: Category(category),
-                status: TaskStatus::Active,
+                category,
+                priority,
+            });
+            let date = date.unwrap_or_else(|| Utc::now().trunc_subsecs(0));
+            let created_by = created_by.unwrap_or_else(current_user);
+            let make_task = |fields: TaskTemplate| -> Result<Task, String> {
+                let category = config.category(&expand_env(
+                    &fields
+                        .category
+                        .unwrap_or_else(|| config.default_category().to_string()),
+                ))?;
+                let (priority, due) = config.task_defaults(&category, fields.priority, due, date);
+                let title = fields.title.trim().to_string();
+                Ok(
+                    Task::builder(title, expand_env(&fields.description), category)
+                        .creation_date(date)
+                        .depends_on(depends_on.clone())
+                        .due(due)
+                        .label(label.clone())
+                        .priority(priority)
+                        .estimate_minutes(estimate)
+                        .assignee(assignee.clone())
+                        .created_by(created_by.clone())
+                        .recurrence(recur)
+                        .build(),
+                )
             };
-            match todo_list.add_task(task) {
-                Ok(_) => println!("Task '{}' added successfully", title),
-                Err(e) => eprintln!("Error: {}", e),
+
+            if let Some(path) = from_file {
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                let built: Vec<(usize, Result<Task, String>)> = parse_task_lines(&content)
+                    .into_iter()
+                    .map(|(line, parsed)| {
+                        let task = parsed.and_then(|parsed| {
+                            make_task(TaskTemplate {
+                                title: parsed.title,
+                                description: parsed.description,
+                                category: parsed.category,
+                                priority: fields.priority,
+                            })
+                        });
+                        (line, task)
+                    })
+                    .collect();
+                if built.is_empty() {
+                    return Err(format!("No tasks found in {}", path.display()));
+                }
+                let valid = built
+                    .iter()
+                    .filter_map(|(_, task)| task.as_ref().ok().cloned())
+                    .collect();
+                let mut added = todo_list.add_each(valid)?.into_iter();
+                let results = built
+                    .into_iter()
+                    .map(|(line, task)| {
+                        task.and_then(|_| added.next().unwrap())
+                            .map_err(|e| format!("Line {}: {}", line, e))
+                    })
+                    .collect();
+                report_each(&reporter, results, "added successfully")?;
+                return Ok(ExitCode::SUCCESS);
             }
+
+            let task = make_task(fields)?;
+            let title = task.title.clone();
+            if !no_dup_check && !confirm_not_duplicate(&todo_list, &task) {
+                println!("Aborted.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            let replaced = if overwrite {
+                todo_list.upsert_task(task, keep_date)?
+            } else {
+                todo_list.add_task(task)?;
+                false
+            };
+            if external_description {
+                todo_list.externalize_description(&title)?;
+            }
+            let action = if replaced { "replaced" } else { "added" };
+            reporter.confirm(format!("Task '{}' {} successfully", title, action));
         }
-        Commands::Done { title } => match todo_list.mark_as_done(&title) {
-            Ok(_) => println!("Task '{}' marked as done", title),
-            Err(e) => eprintln!("Error: {}", e),
-        },
-        Commands::Update { title } => {
-            if let Some(old_task) = todo_list.tasks.get(&title) {
-                println!("Updating task: {}", title);
-
-                println!("Enter new description (press Enter to keep current):");
-                let mut new_description = String::new();
-                std::io::stdin().read_line(&mut new_description).unwrap();
-                let new_description = new_description.trim();
-                let new_description = if new_description.is_empty() {
-                    old_task.description.clone()
+        Commands::Done {
+            titles,
+            predicate,
+            yes,
+        } => match predicate {
+            Some(predicate) => {
+                let matches = todo_list.filter_tasks(&predicate)?;
+                if matches.is_empty() {
+                    println!("No tasks match the given predicate.");
+                    return Ok(ExitCode::SUCCESS);
+                }
+                let titles = matches.iter().map(|task| task.title.clone()).collect();
+                if !confirm_bulk(titles, "marked as done", yes, &reporter) {
+                    return Ok(ExitCode::SUCCESS);
+                }
+                let count = todo_list.mark_done_where(&predicate)?;
+                reporter.confirm(format!("{} task(s) marked as done", count));
+            }
+            None => {
+                let titles = if titles.is_empty() {
+                    let active = todo_list
+                        .iter()
+                        .filter(|task| task.status == TaskStatus::Active)
+                        .collect();
+                    vec![pick_task(active, "Mark as done")?]
                 } else {
-                    new_description.to_string()
+                    titles
                 };
-
-                println!("Enter new date (YYYY-MM-DD HH:MM) (press Enter to keep current):");
-                let mut new_date = String::new();
-                std::io::stdin().read_line(&mut new_date).unwrap();
-                let new_date = new_date.trim();
-                let new_date = if new_date.is_empty() {
-                    old_task.creation_date
+                report_each(
+                    &reporter,
+                    todo_list.mark_all_done(&titles)?,
+                    "marked as done",
+                )?
+            }
+        },
+        Commands::Reopen { title } => {
+            let title = todo_list.resolve_title(&title)?;
+            todo_list.reopen(&title)?;
+            reporter.confirm(format!("Task '{}' reopened", title));
+        }
+        Commands::Touch { title, restamp } => {
+            let title = todo_list.resolve_title(&title)?;
+            if restamp {
+                todo_list.restamp(&title)?;
+            } else {
+                todo_list.touch(&title)?;
+            }
+            reporter.confirm(format!("Task '{}' touched", title));
+        }
+        Commands::Update {
+            title,
+            label,
+            priority,
+            assignee,
+            append_description,
+            no_confirm,
+        } => {
+            let old_task = todo_list.find_task(&title)?;
+            let description = append_description.map(|text| {
+                if old_task.description.is_empty() {
+                    text
                 } else {
-                    parse_date(new_date).unwrap_or(old_task.creation_date)
-                };
-
-                println!("Enter new category (press Enter to keep current):");
-                let mut new_category = String::new();
-                std::io::stdin().read_line(&mut new_category).unwrap();
-                let new_category = new_category.trim();
-                let new_category = if new_category.is_empty() {
-                    old_task.category.clone()
+                    format!("{}\n{}", old_task.description, text)
+                }
+            });
+            let patch = TaskPatch {
+                description,
+                label,
+                priority,
+                assignee,
+                ..TaskPatch::default()
+            };
+            // Flags say exactly what to change; answers typed at the prompts are
+            // shown back before saving, since a typo there is easy to miss.
+            let ask = patch.is_empty() && !no_confirm;
+            let new_task = if patch.is_empty() {
+                prompt_task_update(old_task)
+            } else {
+                patch.apply(old_task)
+            };
+            if !confirm_update(&todo_list, &new_task, ask) {
+                println!("Aborted.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            todo_list.update_task(&title, new_task)?;
+            reporter.confirm(format!("Task '{}' updated successfully", title));
+        }
+        Commands::Edit { title } => {
+            let old_task = todo_list.find_task(&title)?;
+            let new_task = edit_in_editor(old_task)?;
+            if &new_task == old_task {
+                reporter.confirm(format!("Task '{}' unchanged", title));
+            } else {
+                todo_list.update_task(&title, new_task)?;
+                reporter.confirm(format!("Task '{}' updated successfully", title));
+            }
+        }
+        Commands::Delete {
+            titles,
+            predicate,
+            yes,
+        } => match predicate {
+            Some(predicate) => {
+                let matches = todo_list.filter_tasks(&predicate)?;
+                if matches.is_empty() {
+                    println!("No tasks match the given predicate.");
+                    return Ok(ExitCode::SUCCESS);
+                }
+                let titles = matches.iter().map(|task| task.title.clone()).collect();
+                if !confirm_bulk(titles, "deleted", yes, &reporter) {
+                    return Ok(ExitCode::SUCCESS);
+                }
+                let count = todo_list.delete_where(&predicate)?;
+                reporter.confirm(format!("{} task(s) deleted", count));
+            }
+            None => {
+                let titles = if titles.is_empty() {
+                    vec![pick_task(todo_list.get_all_tasks(), "Delete")?]
                 } else {
-                    Category(new_category.to_string())
+                    titles
                 };
-
-                println!("Enter new status (on/done) (press Enter to keep current):");
-                let mut new_status = String::new();
-                std::io::stdin().read_line(&mut new_status).unwrap();
-                let new_status = new_status.trim();
-                let new_status = if new_status.is_empty() {
-                    old_task.status.clone()
+                report_each(
+                    &reporter,
+                    todo_list.delete_all(&titles)?,
+                    "deleted successfully",
+                )?
+            }
+        },
+        Commands::Backup { dest } => {
+            let path = todo_list.backup(dest.as_deref())?;
+            reporter.confirm(format!("Backed up tasks to {}", path.display()));
+        }
+        Commands::Archive => {
+            let count = todo_list.archive_done()?;
+            reporter.confirm(format!(
+                "{} task(s) archived to {}",
+                count,
+                todo_list.archive_path().display()
+            ));
+        }
+        Commands::Purge { older_than, yes } => {
+            if !yes && !reporter.dry_run {
+                return Err("Purging archived tasks cannot be undone; re-run with --yes".into());
+            }
+            let count = todo_list.purge_archive(older_than)?;
+            reporter.confirm(format!("{} archived task(s) purged", count));
+        }
+        Commands::Move {
+            title,
+            dest,
+            on_conflict,
+        } => {
+            let dest_format = StoreFormat::for_path(&dest)?.unwrap_or(store_format);
+            let mut dest_list = TodoList::with_format(dest.clone(), dest_format)?;
+            dest_list.set_dry_run(reporter.dry_run);
+            match todo_list.move_task(&title, &mut dest_list, on_conflict)? {
+                Some(new_title) if new_title != title => reporter.confirm(format!(
+                    "Task '{}' moved to {} as '{}'",
+                    title,
+                    dest.display(),
+                    new_title
+                )),
+                Some(_) => {
+                    reporter.confirm(format!("Task '{}' moved to {}", title, dest.display()))
+                }
+                None => println!(
+                    "Task '{}' already exists in {}, skipped",
+                    title,
+                    dest.display()
+                ),
+            }
+        }
+        Commands::Note { title, text } => {
+            todo_list.add_note(&title, &text)?;
+            reporter.confirm(format!("Note added to task '{}'", title));
+        }
+        Commands::Attach { title, path } => {
+            if !path.contains("://") && !Path::new(&path).exists() {
+                eprintln!("Warning: '{}' does not exist", path);
+            }
+            todo_list.attach(&title, &path)?;
+            reporter.confirm(format!("Attached '{}' to task '{}'", path, title));
+        }
+        Commands::Subtask { action } => match action {
+            SubtaskAction::Add { title, text } => {
+                let index = todo_list.add_subtask(&title, &text)?;
+                reporter.confirm(format!("Subtask {} added to task '{}'", index, title));
+            }
+            SubtaskAction::Done { title, index } => {
+                let state = if todo_list.toggle_subtask(&title, index)? {
+                    "done"
                 } else {
-                    new_status.parse().unwrap_or(old_task.status.clone())
+                    "not done"
                 };
-
-                let new_task = Task {
-                    title: title.clone(),
-                    description: new_description,
-                    creation_date: new_date,
-                    category: new_category,
-                    status: new_status,
-                };
-
-                match todo_list.update_task(&title, new_task) {
-                    Ok(_) => println!("Task '{}' updated successfully", title),
-                    Err(e) => eprintln!("Error: {}", e),
+                reporter.confirm(format!(
+                    "Subtask {} of task '{}' marked as {}",
+                    index, title, state
+                ));
+            }
+        },
+        Commands::Recur { action } => match action {
+            RecurAction::Set { title, rule } => {
+                let title = todo_list.resolve_title(&title)?;
+                todo_list.set_recurrence(&title, Some(rule))?;
+                reporter.confirm(format!("Task '{}' now repeats {}", title, rule));
+            }
+            RecurAction::Clear { title } => {
+                let title = todo_list.resolve_title(&title)?;
+                todo_list.set_recurrence(&title, None)?;
+                reporter.confirm(format!("Task '{}' no longer repeats", title));
+            }
+            RecurAction::Preview { title, n } => {
+                let task = todo_list.find_task(&title)?;
+                if task.recurrence.is_none() {
+                    return Err(format!("Task '{}' does not recur", task.title));
+                }
+                if task.due.is_none() {
+                    return Err(format!(
+                        "Task '{}' repeats but has no due date to count from",
+                        task.title
+                    ));
+                }
+                for occurrence in task.upcoming_occurrences(n) {
+                    println!("{}", format_date(occurrence.with_timezone(&Utc)));
+                }
+            }
+        },
+        Commands::Show { title } => {
+            let task = todo_list.find_task(&title)?;
+            print_task_details(&todo_list, task);
+        }
+        Commands::Log { title, minutes } => {
+            todo_list.log_time(&title, minutes)?;
+            reporter.confirm(format!("Logged {} minute(s) on task '{}'", minutes, title));
+        }
+        Commands::Stats { since: None } => {
+            let (estimated, spent) = todo_list.effort_totals();
+            println!(
+                "Active tasks: {} min estimated, {} min spent",
+                estimated, spent
+            );
+        }
+        Commands::Stats { since: Some(since) } => {
+            let stats = todo_list.stats_since(since);
+            if stats.is_empty() {
+                println!(
+                    "No tasks created or completed since {}.",
+                    format_date(since)
+                );
+                return Ok(ExitCode::SUCCESS);
+            }
+            println!("Since {}:", format_date(since));
+            println!("Created:         {}", stats.created);
+            println!("Completed:       {}", stats.completed);
+            if let Some(rate) = stats.completion_rate() {
+                println!(
+                    "Completion rate: {:.0}% ({} of {} created are done)",
+                    rate * 100.0,
+                    stats.created_and_done,
+                    stats.created
+                );
+            }
+        }
+        Commands::Check => {
+            let issues = todo_list.validate();
+            if issues.is_empty() {
+                println!("No problems found.");
+            }
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            let errors = issues
+                .iter()
+                .filter(|issue| issue.severity == Severity::Error)
+                .count();
+            if errors > 0 {
+                return Err(format!(
+                    "{} error(s) and {} warning(s) found",
+                    errors,
+                    issues.len() - errors
+                ));
+            }
+        }
+        Commands::Dedupe { yes } => {
+            let duplicates = todo_list.duplicates();
+            if duplicates.is_empty() {
+                println!("No duplicate tasks found.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            let merges = duplicates
+                .iter()
+                .flat_map(|(kept, removed)| {
+                    removed
+                        .iter()
+                        .map(move |title| format!("'{}' into '{}'", title, kept))
+                })
+                .collect();
+            if !confirm_bulk(merges, "merged", yes, &reporter) {
+                return Ok(ExitCode::SUCCESS);
+            }
+            for (kept, removed) in todo_list.dedupe()? {
+                for title in removed {
+                    reporter.confirm(format!("Merged '{}' into '{}'", title, kept));
+                }
+            }
+        }
+        Commands::Seed { count, force } => {
+            if !todo_list.is_empty() && !force {
+                return Err(format!(
+                    "{} already has {} task(s); re-run with --force to add samples anyway",
+                    file_path.display(),
+                    todo_list.len()
+                ));
+            }
+            let added = todo_list.add_tasks(sample_tasks(count, Utc::now()))?;
+            reporter.confirm(format!("Added {} sample task(s)", added));
+        }
+        Commands::Categories => {
+            let counts = todo_list.category_counts();
+            if counts.is_empty() {
+                println!("No categories found.");
+            }
+            for (category, count) in counts {
+                println!("{} ({})", category, count);
+            }
+        }
+        Commands::Recategorize { from, to } => {
+            let to = config.category(&to)?;
+            let moved = todo_list.recategorize(&from, to.clone())?;
+            reporter.confirm(format!(
+                "{} task(s) moved from '{}' to '{}'",
+                moved, from, to
+            ));
+        }
+        Commands::Remind { title, at } => {
+            todo_list.set_reminder(&title, at)?;
+            reporter.confirm(format!(
+                "Reminder for task '{}' set to {}",
+                title,
+                format_date(at)
+            ));
+        }
+        Commands::CheckReminders { notify } => {
+            let reminders = todo_list.due_reminders(Utc::now());
+            if reminders.is_empty() {
+                println!("No reminders due.");
+            } else {
+                if notify {
+                    send_notifications(&reminders)?;
                 }
+                print_tasks(&todo_list, reminders, &reporter);
+            }
+        }
+        Commands::Count { predicate } => {
+            let count = match predicate {
+                Some(predicate) => todo_list.filter_tasks(&predicate)?.len(),
+                None => todo_list.len(),
+            };
+            println!("{}", count);
+        }
+        #[cfg(feature = "tui")]
+        Commands::Tui => todo_list::tui::run(&mut todo_list)?,
+        Commands::Select {
+            predicate,
+            saved,
+            age,
+            tsv,
+            json,
+            count_only,
+            fail_on_empty,
+            output,
+        } => {
+            let predicate = match (predicate, saved) {
+                (Some(predicate), _) => predicate,
+                (None, Some(name)) => QueryStore::load(QueryStore::path_for(&file_path))?
+                    .get(&name)?
+                    .to_string(),
+                (None, None) => unreachable!("clap requires a predicate or --saved"),
+            };
+            let filtered_tasks = todo_list
+                .filter_tasks(&predicate)
+                .map_err(|e| format!("Failed to filter tasks: {}", e))?;
+            let filtered_tasks = age.apply(filtered_tasks);
+            let empty = filtered_tasks.is_empty();
+            let reporter = reporter.for_output(output.as_deref());
+            let mut out = open_output(output.as_deref())?;
+            if count_only {
+                writeln!(out, "{} task(s) matched", filtered_tasks.len())
+            } else if tsv {
+                write!(out, "{}", render_tsv(&filtered_tasks))
+            } else if json {
+                write_json_array(filtered_tasks, &mut out)
+            } else if filtered_tasks.is_empty() {
+                writeln!(out, "No tasks match the given predicate.")
             } else {
-                eprintln!("Error: Task with title '{}' not found", title);
+                write_tasks(&mut out, &todo_list, filtered_tasks, &reporter)
+            }
+            .and_then(|()| out.flush())
+            .map_err(output_error)?;
+            if fail_on_empty && empty {
+                return Ok(ExitCode::from(EXIT_NO_MATCHES));
             }
         }
-        Commands::Delete { title } => match todo_list.delete_task(&title) {
-            Ok(_) => println!("Task '{}' deleted successfully", title),
-            Err(e) => eprintln!("Error: {}", e),
-        },
-        Commands::Select { predicate } => match todo_list.filter_tasks(&predicate) {
-            Ok(filtered_tasks) => {
-                if filtered_tasks.is_empty() {
-                    println!("No tasks match the given predicate.");
-                } else {
-                    for task in filtered_tasks {
-                        println!(
-                            "{}: {} ({}) - {} - {}",
-                            task.title,
-                            task.description,
-                            task.status,
-                            task.category,
-                            task.creation_date
-                        );
+        Commands::Template { action } => {
+            let mut store = TemplateStore::load(TemplateStore::path_for(&file_path))?;
+            match action {
+                TemplateAction::Save {
+                    name,
+                    title,
+                    description,
+                    category,
+                    priority,
+                } => {
+                    let template = TaskTemplate {
+                        title,
+                        description,
+                        category,
+                        priority,
+                    };
+                    store.set(&name, template);
+                    if !reporter.dry_run {
+                        store.save()?;
                     }
+                    reporter.confirm(format!("Template '{}' saved", name));
                 }
+                TemplateAction::List => {
+                    let mut empty = true;
+                    for (name, template) in store.iter() {
+                        empty = false;
+                        println!("{}: {} ({})", name, template.title, template.description);
+                    }
+                    if empty {
+                        println!("No templates saved.");
+                    }
+                }
+            }
+        }
+        Commands::Query { action } => {
+            let mut store = QueryStore::load(QueryStore::path_for(&file_path))?;
+            match action {
+                QueryAction::Save { name, predicate } => {
+                    store.set(&name, &predicate)?;
+                    if !reporter.dry_run {
+                        store.save()?;
+                    }
+                    reporter.confirm(format!("Query '{}' saved", name));
+                }
+                QueryAction::Run { name } => {
+                    let tasks = todo_list
+                        .filter_tasks(store.get(&name)?)
+                        .map_err(|e| format!("Failed to filter tasks: {}", e))?;
+                    if tasks.is_empty() {
+                        println!("No tasks match the given predicate.");
+                    } else {
+                        print_tasks(&todo_list, tasks, &reporter);
+                    }
+                }
+            }
+        }
+        Commands::List {
+            format,
+            age,
+            status,
+            tsv,
+            json,
+            print0,
+            html,
+            columns,
+            table,
+            ascii,
+            group_by,
+            sort,
+            done_last,
+            output,
+            #[cfg(feature = "watch")]
+            watch,
+        } => {
+            let reporter = Reporter {
+                columns,
+                ..reporter.for_output(output.as_deref())
+            };
+            // Only fit the table to the terminal it is printed on.
+            let table_width = match terminal_size::terminal_size() {
+                Some((terminal_size::Width(width), _))
+                    if output.is_none() && std::io::stdout().is_terminal() =>
+                {
+                    Some(usize::from(width))
+                }
+                _ => None,
+            };
+            let write_list = |out: &mut dyn Write, todo_list: &TodoList| -> std::io::Result<()> {
+                let mut all_tasks = age.apply(todo_list.filter_by(&status.predicates()));
+                if !sort.is_empty() || done_last {
+                    sort_tasks(&mut all_tasks, &sort, done_last);
+                }
+                if let Some(field) = group_by {
+                    for (key, tasks) in group_tasks(all_tasks, field) {
+                        writeln!(out, "{} ({}):", key, tasks.len())?;
+                        write_tasks(out, todo_list, tasks, &reporter)?;
+                    }
+                } else if tsv {
+                    write!(out, "{}", render_tsv(&all_tasks))?;
+                } else if json {
+                    write_json_array(all_tasks, &mut *out)?;
+                } else if print0 {
+                    for task in all_tasks {
+                        write!(out, "{}\0", task.title)?;
+                    }
+                } else if html {
+                    write!(out, "{}", render_html(all_tasks))?;
+                } else if all_tasks.is_empty() {
+                    writeln!(out, "No tasks found.")?;
+                } else if table {
+                    let style = if ascii {
+                        TableStyle::Ascii
+                    } else {
+                        TableStyle::Unicode
+                    };
+                    write_table(out, todo_list, all_tasks, &reporter, style, table_width)?;
+                } else if let Some(template) = &format {
+                    for task in all_tasks {
+                        writeln!(out, "{}", render_template(template, task))?;
+                    }
+                } else {
+                    write_tasks(out, todo_list, all_tasks, &reporter)?;
+                }
+                out.flush()
+            };
+            #[cfg(feature = "watch")]
+            if watch {
+                return watch_file(&file_path, || {
+                    print!("\x1b[2J\x1b[H");
+                    match TodoList::with_format(file_path.clone(), store_format) {
+                        Ok(todo_list) => write_list(&mut std::io::stdout(), &todo_list)
+                            .expect("Failed to write to stdout"),
+                        Err(e) => print_error(&e, json),
+                    }
+                })
+                .map(|()| ExitCode::SUCCESS);
+            }
+            let mut out = open_output(output.as_deref())?;
+            write_list(&mut out, &todo_list).map_err(output_error)?;
+        }
+        Commands::Overdue => {
+            let overdue = todo_list.tasks_due_before(Utc::now());
+            if overdue.is_empty() {
+                println!("No overdue tasks.");
+            } else {
+                print_tasks(&todo_list, overdue, &reporter);
+            }
+        }
+        Commands::Today => {
+            let today = Local::now().date_naive();
+            let start = local_midnight(today);
+            let end = local_midnight(today.succ_opt().unwrap());
+            let due_today: Vec<&Task> = todo_list
+                .tasks_due_before(end)
+                .into_iter()
+                .filter(|task| task.due.is_some_and(|due| due >= start))
+                .collect();
+            if due_today.is_empty() {
+                println!("No tasks due today.");
+            } else {
+                print_tasks(&todo_list, due_today, &reporter);
             }
-            Err(e) => eprintln!("Error filtering tasks: {}", e),
+        }
+        Commands::Next => match todo_list.next_task() {
+            Some(task) => print_tasks(&todo_list, vec![task], &reporter),
+            None => println!("Nothing to do!"),
         },
-        Commands::List => {
-            let all_tasks = todo_list.get_all_tasks();
-            if all_tasks.is_empty() {
-                println!("No tasks found.");
+        Commands::Changes { since } => {
+            let changed = todo_list.changed_since(since);
+            if changed.is_empty() {
+                println!("No tasks changed since {}.", format_date(since));
             } else {
-                for task in all_tasks {
-                    println!(
-                        "{}: {} ({}) - {} - {}",
-                        task.title,
-                        task.description,
-                        task.status,
-                        task.category,
-                        task.creation_date
-                    );
-                }
+                print_tasks(&todo_list, changed, &reporter);
             }
         }
     }
+    Ok(ExitCode::SUCCESS)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use std::path::PathBuf;
-    use std::sync::atomic::{AtomicUsize, Ordering};
-
-    static COUNTER: AtomicUsize = AtomicUsize::new(0);
-
-    fn get_unique_file_path() -> PathBuf {
-        let counter = COUNTER.fetch_add(1, Ordering::SeqCst);
-        PathBuf::from(format!("test_tasks_{}.json", counter))
-    }
+/// The `--date-format` flag, read before the real parse: date arguments are parsed
+/// while the command line is, so the format has to be set up first.
+fn date_format_flag() -> Option<String> {
+    Cli::command()
+        .ignore_errors(true)
+        .try_get_matches()
+        .ok()?
+        .get_one::<String>("date_format")
+        .cloned()
+}
 
-    fn cleanup_file(path: &PathBuf) {
-        if path.exists() {
-            fs::remove_file(path).expect("Failed to remove test file");
+fn main() -> ExitCode {
+    let config = Config::load();
+    // `Config::load` has already warned about a bad `date_format` in the config file.
+    match date_format_flag() {
+        Some(format) => match set_date_format(&format) {
+            Ok(()) => {
+                if let Err(e) = check_date_format(&format) {
+                    eprintln!("Warning: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Warning: {}; using the default format", e),
+        },
+        None => {
+            if let Some(format) = &config.date_format {
+                let _ = set_date_format(format);
+            }
         }
     }
-
-    fn setup() -> (TodoList, PathBuf) {
-        let file_path = get_unique_file_path();
-        let todo_list = TodoList::new(file_path.clone());
-        (todo_list, file_path)
-    }
-
-    #[test]
-    fn test_add_task() {
-        let (mut todo_list, file_path) = setup();
-        let task = Task::new(
-            "Test Task".to_string(),
-            "Description".to_string(),
-            Category("TestCategory".to_string()),
-        );
-        assert!(todo_list.add_task(task).is_ok());
-        cleanup_file(&file_path);
-    }
-
-    #[test]
-    fn test_mark_as_done() {
-        let (mut todo_list, file_path) = setup();
-        let task = Task::new(
-            "Test Task".to_string(),
-            "Description".to_string(),
-            Category("TestCategory".to_string()),
-        );
-        todo_list.add_task(task).unwrap();
-        assert!(todo_list.mark_as_done("Test Task").is_ok());
-        assert_eq!(
-            todo_list.tasks.get("Test Task").unwrap().status,
-            TaskStatus::Done
-        );
-        cleanup_file(&file_path);
-    }
-
-    #[test]
-    fn test_delete_task() {
-        let (mut todo_list, file_path) = setup();
-        let task = Task::new(
-            "Test Task".to_string(),
-            "Description".to_string(),
-            Category("TestCategory".to_string()),
-        );
-        todo_list.add_task(task).unwrap();
-        assert!(todo_list.delete_task("Test Task").is_ok());
-        assert!(todo_list.tasks.is_empty());
-        cleanup_file(&file_path);
-    }
-
-    #[test]
-    fn test_filter_tasks() {
-        let (mut todo_list, file_path) = setup();
-        let task1 = Task::new(
-            "Task 1".to_string(),
-            "Description 1".to_string(),
-            Category("Category1".to_string()),
-        );
-        let task2 = Task::new(
-            "Task 2".to_string(),
-            "Description 2".to_string(),
-            Category("Category2".to_string()),
-        );
-        todo_list.add_task(task1).unwrap();
-        todo_list.add_task(task2).unwrap();
-
-        let filtered = todo_list.filter_tasks(r#"category = "Category1""#).unwrap();
-        assert_eq!(filtered.len(), 1);
-        assert_eq!(filtered[0].title, "Task 1");
-
-        let filtered = todo_list
-            .filter_tasks(r#"description like "Description""#)
-            .unwrap();
-        assert_eq!(filtered.len(), 2);
-
-        assert!(todo_list.filter_tasks("invalid predicate").is_err());
-
-        cleanup_file(&file_path);
-    }
-
-    #[test]
-    fn test_predicate_parsing() {
-        let (_todo_list, file_path) = setup();
-        assert_eq!(
-            "category = TestCategory".parse::<Predicate>().unwrap(),
-            Predicate::Category("TestCategory".to_string())
-        );
-        assert_eq!(
-            "status = on".parse::<Predicate>().unwrap(),
-            Predicate::Status(TaskStatus::Active)
-        );
-        assert!("date < 2023-05-20 10:00".parse::<Predicate>().is_ok());
-        assert_eq!(
-            "description like \"test\"".parse::<Predicate>().unwrap(),
-            Predicate::DescriptionContains("test".to_string())
-        );
-        cleanup_file(&file_path);
-    }
-
-    #[test]
-    fn test_update_task() {
-        let (mut todo_list, file_path) = setup();
-        let task = Task::new(
-            "Test Task".to_string(),
-            "Description".to_string(),
-            Category("TestCategory".to_string()),
-        );
-        todo_list.add_task(task).unwrap();
-
-        let updated_task = Task {
-            title: "Test Task".to_string(),
-            description: "Updated Description".to_string(),
-            creation_date: Local::now(),
-            category: Category("UpdatedCategory".to_string()),
-            status: TaskStatus::Done,
-        };
-
-        assert!(todo_list.update_task("Test Task", updated_task).is_ok());
-
-        let updated = todo_list.tasks.get("Test Task").unwrap();
-        assert_eq!(updated.description, "Updated Description");
-        assert_eq!(updated.category.0, "UpdatedCategory");
-        assert_eq!(updated.status, TaskStatus::Done);
-        cleanup_file(&file_path);
-    }
-
-    #[test]
-    fn test_category_fromstr() {
-        let (_todo_list, file_path) = setup();
-        let category: Category = "TestCategory".parse().unwrap();
-        assert_eq!(category.0, "TestCategory");
-        cleanup_file(&file_path);
-    }
-
-    #[test]
-    fn test_taskstatus_fromstr() {
-        let (_todo_list, file_path) = setup();
-        assert_eq!("on".parse::<TaskStatus>().unwrap(), TaskStatus::Active);
-        assert_eq!("done".parse::<TaskStatus>().unwrap(), TaskStatus::Done);
-        assert!("invalid".parse::<TaskStatus>().is_err());
-        cleanup_file(&file_path);
+    let cli = Cli::parse();
+    let json_errors = cli.json_errors || cli.command.json_output();
+    match run(cli, &config) {
+        Ok(code) => code,
+        Err(e) => {
+            print_error(&e, json_errors);
+            ExitCode::FAILURE
+        }
     }
 }
@@ -0,0 +1,572 @@
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::date::{parse_date, parse_duration};
+use crate::task::{Task, TaskStatus};
+
+#[derive(Debug, PartialEq)]
+pub enum Predicate {
+    Category(String),
+    Status(TaskStatus),
+    Assignee(String),
+    CreatedBy(String),
+    DateBefore(DateTime<Utc>),
+    DateAfter(DateTime<Utc>),
+    ModifiedBefore(DateTime<Utc>),
+    ModifiedAfter(DateTime<Utc>),
+    /// `due within "3d"`: active tasks due between now and now + the duration.
+    DueWithin(Duration),
+    /// `description like "text"`: case-insensitive substring match.
+    DescriptionContains(String),
+    DescriptionEquals(String),
+    /// `title like "text"`: case-insensitive substring match.
+    TitleContains(String),
+    TitleEquals(String),
+    /// `due is set`: the optional field has a value (for `description` and
+    /// `attachments`, is non-empty). `is null` is parsed as its negation, and
+    /// `has due` is shorthand for `due is set`.
+    FieldSet(String),
+    /// `category like "work/*"`: `*` matches any run of characters, `?` a single one.
+    CategoryGlob(String),
+    /// `category ilike "Work"`: like `=` but ignoring case, as status matching does.
+    CategoryIgnoreCase(String),
+    /// Tasks with a due date before the given time. Not part of the predicate syntax;
+    /// built by `list --overdue-only`.
+    DueBefore(DateTime<Utc>),
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    fn from_parts(field: &str, operator: &str, value: &str) -> Result<Self, String> {
+        if operator == "!=" {
+            return match Predicate::from_parts(field, "=", value) {
+                Ok(predicate) => Ok(Predicate::Not(Box::new(predicate))),
+                Err(_) => Err(format!("Operator != is not supported for {}", field)),
+            };
+        }
+        let field = field.to_lowercase();
+        let value = value.to_string();
+
+        match (field.as_str(), operator) {
+            ("category", "=") => Ok(Predicate::Category(value)),
+            ("assignee", "=") => Ok(Predicate::Assignee(value)),
+            ("created_by", "=") => Ok(Predicate::CreatedBy(value)),
+            ("status", "=") => TaskStatus::from_str(&value)
+                .map(Predicate::Status)
+                .map_err(|e| e.to_string()),
+            ("date", "<") => parse_date(&value).map(Predicate::DateBefore),
+            ("date", ">") => parse_date(&value).map(Predicate::DateAfter),
+            ("modified", "<") => parse_date(&value).map(Predicate::ModifiedBefore),
+            ("modified", ">") => parse_date(&value).map(Predicate::ModifiedAfter),
+            ("due", "within") => parse_duration(&value).map(Predicate::DueWithin),
+            ("description", "like") => Ok(Predicate::DescriptionContains(value)),
+            ("description", "=") => Ok(Predicate::DescriptionEquals(value)),
+            ("title", "like") => Ok(Predicate::TitleContains(value)),
+            ("title", "=") => Ok(Predicate::TitleEquals(value)),
+            (
+                "due" | "remind_at" | "label" | "estimate" | "assignee" | "description"
+                | "attachments",
+                "is",
+            ) => {
+                let is_set = Predicate::FieldSet(field.clone());
+                match value.to_lowercase().as_str() {
+                    "set" => Ok(is_set),
+                    "null" => Ok(Predicate::Not(Box::new(is_set))),
+                    _ => Err(format!(
+                        "Expected 'is set' or 'is null' for {}, got '{}'",
+                        field, value
+                    )),
+                }
+            }
+            ("category", "like") => Ok(Predicate::CategoryGlob(value)),
+            ("category", "ilike") => Ok(Predicate::CategoryIgnoreCase(value)),
+            _ => Err(format!("Unknown predicate: {}", field)),
+        }
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Predicate::Category(category) => &task.category.0 == category,
+            Predicate::Status(status) => &task.status == status,
+            Predicate::Assignee(assignee) => task.assignee.as_ref() == Some(assignee),
+            Predicate::CreatedBy(user) => &task.created_by == user,
+            Predicate::DateBefore(date) => task.creation_date < *date,
+            Predicate::DateAfter(date) => task.creation_date > *date,
+            Predicate::ModifiedBefore(date) => task.modified_at() < *date,
+            Predicate::ModifiedAfter(date) => task.modified_at() > *date,
+            Predicate::DueWithin(window) => {
+                let now = Utc::now();
+                task.status == TaskStatus::Active
+                    && task
+                        .due
+                        .is_some_and(|due| due >= now && due <= now + *window)
+            }
+            Predicate::DescriptionContains(text) => task
+                .description
+                .to_lowercase()
+                .contains(&text.to_lowercase()),
+            Predicate::DescriptionEquals(text) => &task.description == text,
+            Predicate::TitleContains(text) => {
+                task.title.to_lowercase().contains(&text.to_lowercase())
+            }
+            Predicate::TitleEquals(text) => &task.title == text,
+            Predicate::FieldSet(field) => match field.as_str() {
+                "due" => task.due.is_some(),
+                "remind_at" => task.remind_at.is_some(),
+                "label" => task.label.is_some(),
+                "assignee" => task.assignee.is_some(),
+                "estimate" => task.estimate_minutes.is_some(),
+                "description" => !task.description.is_empty(),
+                "attachments" => !task.attachments.is_empty(),
+                _ => false,
+            },
+            Predicate::CategoryGlob(pattern) => glob_match(pattern, &task.category.0),
+            Predicate::CategoryIgnoreCase(category) => {
+                task.category.0.to_lowercase() == category.to_lowercase()
+            }
+            Predicate::DueBefore(date) => task.due.is_some_and(|due| due < *date),
+            Predicate::Not(predicate) => !predicate.matches(task),
+        }
+    }
+}
+
+/// Matches `text` against a glob `pattern` supporting `*` and `?`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` seen and the text index it was tried against,
+    // so a mismatch can backtrack by letting that star swallow one more char.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl FromStr for Predicate {
+    type Err = String;
+
+    /// Parses exactly one clause with the same grammar as `parse_predicates`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut predicates = parse_predicates(s)?;
+        if predicates.len() != 1 {
+            return Err(format!(
+                "Expected a single predicate, found {}",
+                predicates.len()
+            ));
+        }
+        Ok(predicates.remove(0))
+    }
+}
+
+/// Parses clauses joined by `and` (or just whitespace). Any other text between,
+/// before or after the clauses is an error, so a mistyped clause is never dropped.
+pub fn parse_predicates(predicate: &str) -> Result<Vec<Predicate>, String> {
+    // `is set` / `is null` take a bare keyword; every other operator a value in
+    // double or single quotes. `has <field>` stands on its own.
+    static CLAUSES: OnceLock<Regex> = OnceLock::new();
+    let re = CLAUSES.get_or_init(|| {
+        Regex::new(
+            r#"\bhas\s+(\w+)|(\w+)\s*(?:(!=|=|<|>|ilike|like|within)\s*(?:"([^"]*)"|'([^']*)')|\bis\s+(\w+))"#,
+        )
+        .unwrap()
+    });
+    let captures: Vec<_> = re.captures_iter(predicate).collect();
+
+    if captures.is_empty() {
+        return Err("Invalid predicate format".to_string());
+    }
+
+    let mut end = 0;
+    for (i, cap) in captures.iter().enumerate() {
+        let clause = cap.get(0).unwrap();
+        check_gap(predicate, end, clause.start(), i > 0)?;
+        end = clause.end();
+    }
+    check_gap(predicate, end, predicate.len(), false)?;
+
+    captures
+        .into_iter()
+        .map(|cap| {
+            if let Some(field) = cap.get(1) {
+                return Predicate::from_parts(field.as_str(), "is", "set");
+            }
+            match cap.get(3) {
+                Some(operator) => {
+                    let value = cap.get(4).or_else(|| cap.get(5)).unwrap().as_str();
+                    Predicate::from_parts(&cap[2], operator.as_str(), value)
+                }
+                None => Predicate::from_parts(&cap[2], "is", &cap[6]),
+            }
+        })
+        .collect()
+}
+
+/// Checks that `predicate[start..end]` is blank, or the `and` joining two clauses if
+/// `joins` is set.
+fn check_gap(predicate: &str, start: usize, end: usize, joins: bool) -> Result<(), String> {
+    let gap = &predicate[start..end];
+    let text = gap.trim();
+    if text.is_empty() || (joins && text.eq_ignore_ascii_case("and")) {
+        return Ok(());
+    }
+    let offset = start + (gap.len() - gap.trim_start().len());
+    Err(format!(
+        "Unexpected '{}' at position {} in predicate",
+        text,
+        predicate[..offset].chars().count() + 1
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{cleanup_file, setup};
+
+    #[test]
+    fn test_predicate_parsing() {
+        let (_todo_list, file_path) = setup();
+        assert_eq!(
+            "category = 'TestCategory'".parse::<Predicate>().unwrap(),
+            Predicate::Category("TestCategory".to_string())
+        );
+        assert_eq!(
+            "status = 'on'".parse::<Predicate>().unwrap(),
+            Predicate::Status(TaskStatus::Active)
+        );
+        assert!("date < '2023-05-20 10:00'".parse::<Predicate>().is_ok());
+        assert_eq!(
+            "description like \"test\"".parse::<Predicate>().unwrap(),
+            Predicate::DescriptionContains("test".to_string())
+        );
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_predicate_quoted_values_with_spaces() {
+        let expected = Predicate::Category("My Project".to_string());
+        assert_eq!(
+            r#"category = "My Project""#.parse::<Predicate>().unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_predicates(r#"category = "My Project""#).unwrap(),
+            vec![expected]
+        );
+        assert!("category = My Project".parse::<Predicate>().is_err());
+    }
+
+    #[test]
+    fn test_single_quoted_values() {
+        let work = vec![Predicate::Category("work".to_string())];
+        assert_eq!(parse_predicates("category = 'work'").unwrap(), work);
+        assert_eq!(parse_predicates(r#"category = "work""#).unwrap(), work);
+        assert_eq!(
+            "category = 'My Project'".parse::<Predicate>().unwrap(),
+            Predicate::Category("My Project".to_string())
+        );
+        assert_eq!(
+            parse_predicates(r#"title like "it's" and description = 'say "hi"'"#).unwrap(),
+            vec![
+                Predicate::TitleContains("it's".to_string()),
+                Predicate::DescriptionEquals(r#"say "hi""#.to_string()),
+            ]
+        );
+        assert!(parse_predicates(r#"category = 'work""#).is_err());
+    }
+
+    #[test]
+    fn test_status_predicate_aliases() {
+        for alias in ["on", "active", "a", "ACTIVE"] {
+            assert_eq!(
+                parse_predicates(&format!(r#"status = "{}""#, alias)).unwrap(),
+                vec![Predicate::Status(TaskStatus::Active)]
+            );
+        }
+        for alias in ["done", "d", "D"] {
+            assert_eq!(
+                parse_predicates(&format!(r#"status = "{}""#, alias)).unwrap(),
+                vec![Predicate::Status(TaskStatus::Done)]
+            );
+        }
+        assert!(parse_predicates(r#"status = "x""#).is_err());
+    }
+
+    #[test]
+    fn test_category_glob() {
+        assert!(glob_match("work/*", "work/proj1"));
+        assert!(glob_match("work/*", "work/"));
+        assert!(!glob_match("work/*", "home/proj1"));
+        assert!(glob_match("*/proj?", "work/proj2"));
+        assert!(!glob_match("*/proj?", "work/proj12"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("a*b*c", "axxbyy"));
+        assert!(glob_match("work", "work"));
+        assert!(!glob_match("work", "work/proj1"));
+
+        let predicates = parse_predicates(r#"category like "work/*""#).unwrap();
+        assert_eq!(
+            predicates,
+            vec![Predicate::CategoryGlob("work/*".to_string())]
+        );
+        let task = Task::new(
+            "Task".to_string(),
+            "Description".to_string(),
+            crate::task::Category("work/proj1".to_string()),
+        );
+        assert!(predicates[0].matches(&task));
+    }
+
+    #[test]
+    fn test_category_ilike() {
+        let task = Task::new(
+            "Task".to_string(),
+            "Description".to_string(),
+            crate::task::Category("Work".to_string()),
+        );
+        let exact = parse_predicates(r#"category = "work""#).unwrap();
+        assert!(!exact[0].matches(&task));
+
+        let predicates = parse_predicates(r#"category ilike "WORK" and status = "ON""#).unwrap();
+        assert_eq!(
+            predicates[0],
+            Predicate::CategoryIgnoreCase("WORK".to_string())
+        );
+        assert!(predicates.iter().all(|p| p.matches(&task)));
+        assert!(!parse_predicates("category ilike 'work/*'").unwrap()[0].matches(&task));
+        assert_eq!(
+            "category ilike 'work'".parse::<Predicate>().unwrap(),
+            Predicate::CategoryIgnoreCase("work".to_string())
+        );
+        assert!(parse_predicates(r#"title ilike "task""#).is_err());
+    }
+
+    #[test]
+    fn test_description_like_ignores_case() {
+        let task = Task::new(
+            "Task".to_string(),
+            "Description with TODO items".to_string(),
+            crate::task::Category("x".to_string()),
+        );
+        for text in ["descr", "todo", "WITH todo", "Description"] {
+            let predicates = parse_predicates(&format!(r#"description like "{}""#, text)).unwrap();
+            assert!(predicates[0].matches(&task), "{}", text);
+        }
+        let missing = parse_predicates(r#"description like "done""#).unwrap();
+        assert!(!missing[0].matches(&task));
+    }
+
+    #[test]
+    fn test_title_predicates() {
+        let task = Task::new(
+            "Weekly Meeting notes".to_string(),
+            "Description".to_string(),
+            crate::task::Category("x".to_string()),
+        );
+        let like = parse_predicates(r#"title like "meeting" and status = "on""#).unwrap();
+        assert_eq!(like[0], Predicate::TitleContains("meeting".to_string()));
+        assert!(like.iter().all(|p| p.matches(&task)));
+        assert!(!parse_predicates(r#"title like "standup""#).unwrap()[0].matches(&task));
+
+        let exact = parse_predicates(r#"title = "Weekly Meeting notes""#).unwrap();
+        assert!(exact[0].matches(&task));
+        assert!(!parse_predicates(r#"title = "weekly meeting notes""#).unwrap()[0].matches(&task));
+        assert!(!parse_predicates(r#"title != "Weekly Meeting notes""#).unwrap()[0].matches(&task));
+    }
+
+    #[test]
+    fn test_created_by_predicate() {
+        let mut task = Task::new(
+            "Task".to_string(),
+            "Description".to_string(),
+            crate::task::Category("x".to_string()),
+        );
+        task.created_by = "alice".to_string();
+        let alice = parse_predicates(r#"created_by = "alice""#).unwrap();
+        assert_eq!(alice[0], Predicate::CreatedBy("alice".to_string()));
+        assert!(alice[0].matches(&task));
+        assert!(!parse_predicates(r#"created_by = "bob""#).unwrap()[0].matches(&task));
+        assert!(parse_predicates(r#"created_by != "bob""#).unwrap()[0].matches(&task));
+    }
+
+    #[test]
+    fn test_assignee_predicate() {
+        let mut task = Task::new(
+            "Task".to_string(),
+            "Description".to_string(),
+            crate::task::Category("x".to_string()),
+        );
+        let alice = parse_predicates(r#"assignee = "alice""#).unwrap();
+        let unassigned = parse_predicates("assignee is null").unwrap();
+        assert!(!alice[0].matches(&task));
+        assert!(unassigned[0].matches(&task));
+
+        task.assignee = Some("alice".to_string());
+        assert!(alice[0].matches(&task));
+        assert!(!unassigned[0].matches(&task));
+        task.assignee = Some("bob".to_string());
+        assert!(!alice[0].matches(&task));
+    }
+
+    #[test]
+    fn test_due_within() {
+        let mut task = Task::new(
+            "Task".to_string(),
+            "Description".to_string(),
+            crate::task::Category("x".to_string()),
+        );
+        let three_days = parse_predicates(r#"due within "3d""#).unwrap();
+        let one_day = parse_predicates(r#"due within "1d""#).unwrap();
+        assert_eq!(three_days, vec![Predicate::DueWithin(Duration::days(3))]);
+        assert!(!three_days[0].matches(&task));
+
+        task.due = Some(Utc::now() + Duration::days(2));
+        assert!(three_days[0].matches(&task));
+        assert!(!one_day[0].matches(&task));
+
+        task.status = TaskStatus::Done;
+        assert!(!three_days[0].matches(&task));
+
+        task.status = TaskStatus::Active;
+        task.due = Some(Utc::now() - Duration::hours(1));
+        assert!(!three_days[0].matches(&task));
+        assert!(parse_predicates(r#"due within "soon""#).is_err());
+    }
+
+    #[test]
+    fn test_missing_field_predicates() {
+        let mut task = Task::new(
+            "Task".to_string(),
+            String::new(),
+            crate::task::Category("x".to_string()),
+        );
+        let no_due = parse_predicates("due is null").unwrap();
+        let has_due = parse_predicates("due is set").unwrap();
+        let empty_description = parse_predicates(r#"description = """#).unwrap();
+        assert!(no_due[0].matches(&task));
+        assert!(!has_due[0].matches(&task));
+        assert!(empty_description[0].matches(&task));
+
+        task.due = Some(Utc::now());
+        task.description = "Filled in".to_string();
+        assert!(!no_due[0].matches(&task));
+        assert!(has_due[0].matches(&task));
+        assert!(!empty_description[0].matches(&task));
+
+        let combined = parse_predicates(r#"due is null and category = "x""#).unwrap();
+        assert_eq!(combined.len(), 2);
+        assert!("label is null".parse::<Predicate>().is_ok());
+        assert!(parse_predicates("due is maybe").is_err());
+        assert!(parse_predicates("status is set").is_err());
+    }
+
+    #[test]
+    fn test_has_attachments_predicate() {
+        let mut task = Task::new(
+            "Task".to_string(),
+            String::new(),
+            crate::task::Category("x".to_string()),
+        );
+        let has = parse_predicates(r#"has attachments and category = "x""#).unwrap();
+        assert_eq!(has[0], Predicate::FieldSet("attachments".to_string()));
+        assert_eq!("has attachments".parse::<Predicate>().unwrap(), has[0],);
+        assert!(parse_predicates("attachments is null").unwrap()[0].matches(&task));
+        assert!(!has[0].matches(&task));
+
+        task.attachments.push("spec.pdf".to_string());
+        assert!(has[0].matches(&task));
+        assert!(parse_predicates("has status").is_err());
+    }
+
+    #[test]
+    fn test_not_equal_predicate() {
+        let predicates = parse_predicates(r#"category != "x""#).unwrap();
+        assert_eq!(
+            predicates,
+            vec![Predicate::Not(Box::new(Predicate::Category(
+                "x".to_string()
+            )))]
+        );
+        let mut task = Task::new(
+            "Task".to_string(),
+            "Description".to_string(),
+            crate::task::Category("x".to_string()),
+        );
+        assert!(!predicates[0].matches(&task));
+        task.category = crate::task::Category("y".to_string());
+        assert!(predicates[0].matches(&task));
+
+        let not_done = "status != 'done'".parse::<Predicate>().unwrap();
+        assert!(not_done.matches(&task));
+        task.status = TaskStatus::Done;
+        assert!(!not_done.matches(&task));
+
+        assert!(parse_predicates(r#"date != "2023-05-20 10:00""#).is_err());
+    }
+
+    #[test]
+    fn test_unparsed_text_is_rejected() {
+        let err = parse_predicates(r#"category = "x" garbage"#).unwrap_err();
+        assert_eq!(err, "Unexpected 'garbage' at position 16 in predicate");
+        let err = parse_predicates(r#"oops category = "x""#).unwrap_err();
+        assert!(err.contains("'oops' at position 1"), "{}", err);
+        let err = parse_predicates(r#"category = "x" or status = "on""#).unwrap_err();
+        assert!(err.contains("'or' at position 16"), "{}", err);
+        let err = parse_predicates(r#"category = "x" and status = on"#).unwrap_err();
+        assert!(err.contains("'and status = on'"), "{}", err);
+        assert!(parse_predicates(r#"and category = "x""#).is_err());
+
+        for valid in [
+            r#"  category = "x"  "#,
+            r#"category = "x" AND status = "on""#,
+            r#"category = "x" status = "on""#,
+        ] {
+            assert!(parse_predicates(valid).is_ok(), "{}", valid);
+        }
+    }
+
+    #[test]
+    fn test_from_str_agrees_with_parse_predicates() {
+        for input in [
+            r#"category = "work""#,
+            "category = 'My Project'",
+            "status != 'done'",
+            "label is null",
+            "has attachments",
+            "category = work",
+            "category = My Project",
+            r#"category = "x" garbage"#,
+            "status = on",
+            "",
+        ] {
+            assert_eq!(
+                input.parse::<Predicate>(),
+                parse_predicates(input).map(|mut predicates| predicates.remove(0)),
+                "{}",
+                input
+            );
+        }
+        let err = r#"category = "x" and status = "on""#.parse::<Predicate>().unwrap_err();
+        assert_eq!(err, "Expected a single predicate, found 2");
+    }
+}
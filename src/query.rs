@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::predicate::parse_predicates;
+use crate::storage::temp_path_for;
+
+/// Named predicates saved for reuse, kept in a sidecar file next to the list
+/// (`tasks.json` -> `tasks.queries.json`).
+#[derive(Debug)]
+pub struct QueryStore {
+    path: PathBuf,
+    queries: BTreeMap<String, String>,
+}
+
+impl QueryStore {
+    pub fn path_for(list_path: &Path) -> PathBuf {
+        list_path.with_extension("queries.json")
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let queries = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid saved queries in {}: {}", path.display(), e))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(QueryStore { path, queries })
+    }
+
+    /// Saves `predicate` under `name`, replacing any query already saved with that
+    /// name. The predicate is checked first so broken queries are never stored.
+    pub fn set(&mut self, name: &str, predicate: &str) -> Result<(), String> {
+        parse_predicates(predicate)?;
+        self.queries.insert(name.to_string(), predicate.to_string());
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<&str, String> {
+        match self.queries.get(name) {
+            Some(predicate) => Ok(predicate),
+            None if self.queries.is_empty() => {
+                Err(format!("No saved query named '{}' (none saved yet)", name))
+            }
+            None => Err(format!(
+                "No saved query named '{}'. Saved queries: {}",
+                name,
+                self.names().collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.queries.keys().map(String::as_str)
+    }
+
+    /// Writes the queries via a temp file and rename, like `TodoList::save`.
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.queries)
+            .map_err(|e| format!("Failed to serialize queries: {}", e))?;
+        let tmp_path = temp_path_for(&self.path);
+        fs::write(&tmp_path, content)
+            .map_err(|e| format!("Failed to write to temp file: {}", e))?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| format!("Failed to rename temp file: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::cleanup_file;
+
+    #[test]
+    fn test_query_store_roundtrip() {
+        let path = PathBuf::from("test_queries.queries.json");
+        cleanup_file(&path);
+        let mut store = QueryStore::load(path.clone()).unwrap();
+        assert!(store.get("work").unwrap_err().contains("none saved"));
+
+        store
+            .set("work", r#"category = "work" and status = "on""#)
+            .unwrap();
+        assert!(store.set("broken", "category ~ work").is_err());
+        store.save().unwrap();
+
+        let store = QueryStore::load(path.clone()).unwrap();
+        assert_eq!(
+            store.get("work").unwrap(),
+            r#"category = "work" and status = "on""#
+        );
+        let missing = store.get("home").unwrap_err();
+        assert!(missing.contains("'home'") && missing.contains("work"));
+        assert_eq!(store.names().collect::<Vec<_>>(), ["work"]);
+        cleanup_file(&path);
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Days, Months, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurUnit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// How often a task repeats, e.g. every 2 weeks. Stored as its string form
+/// (`weekly`, `2w`, ...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Recurrence {
+    pub interval: u32,
+    pub unit: RecurUnit,
+}
+
+impl Recurrence {
+    /// The `n`th occurrence counting from `anchor` (which is occurrence 0). Months and
+    /// years are counted on the calendar, so a monthly task due on the 31st falls on
+    /// the last day of shorter months. `None` if the date would be out of range.
+    pub fn nth_after<Tz: TimeZone>(&self, anchor: &DateTime<Tz>, n: u32) -> Option<DateTime<Tz>> {
+        let steps = self.interval.checked_mul(n)?;
+        let anchor = anchor.clone();
+        match self.unit {
+            RecurUnit::Day => anchor.checked_add_days(Days::new(steps.into())),
+            RecurUnit::Week => anchor.checked_add_days(Days::new(u64::from(steps) * 7)),
+            RecurUnit::Month => anchor.checked_add_months(Months::new(steps)),
+            RecurUnit::Year => anchor.checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (adverb, suffix) = match self.unit {
+            RecurUnit::Day => ("daily", "d"),
+            RecurUnit::Week => ("weekly", "w"),
+            RecurUnit::Month => ("monthly", "mo"),
+            RecurUnit::Year => ("yearly", "y"),
+        };
+        if self.interval == 1 {
+            write!(f, "{}", adverb)
+        } else {
+            write!(f, "{}{}", self.interval, suffix)
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    /// Accepts `daily`, `weekly`, `monthly` and `yearly` (also cron's `@daily`,
+    /// `@weekly`, `@monthly`, `@yearly` and `@annually`), or a count followed by `d`,
+    /// `w`, `mo` or `y` (e.g. `2w`, `3mo`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let invalid = || {
+            format!(
+                "Invalid recurrence '{}': expected daily, weekly, monthly, yearly \
+                 (or @daily, ...) or a count with d, w, mo or y (e.g. 2w)",
+                s
+            )
+        };
+        let (interval, unit) = match s.as_str() {
+            "daily" | "@daily" => (1, RecurUnit::Day),
+            "weekly" | "@weekly" => (1, RecurUnit::Week),
+            "monthly" | "@monthly" => (1, RecurUnit::Month),
+            "yearly" | "@yearly" | "@annually" => (1, RecurUnit::Year),
+            _ => {
+                let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+                let unit = match &s[split..] {
+                    "d" => RecurUnit::Day,
+                    "w" => RecurUnit::Week,
+                    "mo" => RecurUnit::Month,
+                    "y" => RecurUnit::Year,
+                    _ => return Err(invalid()),
+                };
+                (s[..split].parse().map_err(|_| invalid())?, unit)
+            }
+        };
+        if interval == 0 {
+            return Err(invalid());
+        }
+        Ok(Recurrence { interval, unit })
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_recurrence_parsing() {
+        let weekly: Recurrence = "Weekly".parse().unwrap();
+        assert_eq!(
+            weekly,
+            Recurrence {
+                interval: 1,
+                unit: RecurUnit::Week
+            }
+        );
+        assert_eq!(weekly.to_string(), "weekly");
+        assert_eq!("2w".parse::<Recurrence>().unwrap().to_string(), "2w");
+        assert_eq!("3mo".parse::<Recurrence>().unwrap().unit, RecurUnit::Month);
+        assert_eq!("1d".parse::<Recurrence>().unwrap().to_string(), "daily");
+        assert_eq!("@Daily".parse::<Recurrence>().unwrap().to_string(), "daily");
+        assert_eq!(
+            "@annually".parse::<Recurrence>().unwrap().to_string(),
+            "yearly"
+        );
+        for invalid in ["", "0d", "w", "2m", "fortnightly", "-1d", "@hourly", "@2w"] {
+            assert!(invalid.parse::<Recurrence>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_nth_after() {
+        let anchor = Utc.with_ymd_and_hms(2024, 1, 31, 9, 0, 0).unwrap();
+        let monthly: Recurrence = "monthly".parse().unwrap();
+        assert_eq!(
+            monthly.nth_after(&anchor, 1).unwrap().to_rfc3339(),
+            "2024-02-29T09:00:00+00:00"
+        );
+        assert_eq!(
+            monthly.nth_after(&anchor, 2).unwrap().to_rfc3339(),
+            "2024-03-31T09:00:00+00:00"
+        );
+        let fortnightly: Recurrence = "2w".parse().unwrap();
+        assert_eq!(
+            fortnightly.nth_after(&anchor, 1).unwrap().to_rfc3339(),
+            "2024-02-14T09:00:00+00:00"
+        );
+        let yearly: Recurrence = "yearly".parse().unwrap();
+        assert_eq!(yearly.nth_after(&anchor, 0), Some(anchor));
+        assert_eq!(serde_json::to_string(&fortnightly).unwrap(), r#""2w""#);
+    }
+
+    #[test]
+    fn test_end_of_month_occurrences() {
+        let day = |date: Option<DateTime<Utc>>| date.unwrap().format("%F").to_string();
+        let monthly: Recurrence = "@monthly".parse().unwrap();
+        let jan_31 = Utc.with_ymd_and_hms(2023, 1, 31, 9, 0, 0).unwrap();
+        let days: Vec<String> = (1..=4)
+            .map(|n| day(monthly.nth_after(&jan_31, n)))
+            .collect();
+        // Each occurrence counts from the anchor, so a short month doesn't pull the
+        // later ones back to the 28th.
+        assert_eq!(
+            days,
+            ["2023-02-28", "2023-03-31", "2023-04-30", "2023-05-31"]
+        );
+
+        let quarterly: Recurrence = "3mo".parse().unwrap();
+        let nov_30 = Utc.with_ymd_and_hms(2023, 11, 30, 9, 0, 0).unwrap();
+        assert_eq!(day(quarterly.nth_after(&nov_30, 1)), "2024-02-29");
+
+        let yearly: Recurrence = "@yearly".parse().unwrap();
+        let leap_day = Utc.with_ymd_and_hms(2024, 2, 29, 9, 0, 0).unwrap();
+        let years: Vec<String> = (1..=4)
+            .map(|n| day(yearly.nth_after(&leap_day, n)))
+            .collect();
+        assert_eq!(
+            years,
+            ["2025-02-28", "2026-02-28", "2027-02-28", "2028-02-29"]
+        );
+    }
+}
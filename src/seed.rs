@@ -0,0 +1,90 @@
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+
+use crate::task::{Category, Priority, Task, TaskStatus};
+
+const ACTIONS: [&str; 8] = [
+    "Review",
+    "Write",
+    "Plan",
+    "Fix",
+    "Call about",
+    "Clean up",
+    "Research",
+    "Book",
+];
+const SUBJECTS: [&str; 9] = [
+    "quarterly report",
+    "team offsite",
+    "kitchen sink",
+    "dentist appointment",
+    "onboarding docs",
+    "flight tickets",
+    "garden shed",
+    "release notes",
+    "tax paperwork",
+];
+const CATEGORIES: [&str; 5] = ["work", "home", "errands", "health", "learning"];
+const LABELS: [&str; 4] = ["red", "green", "blue", "yellow"];
+
+/// Builds `count` plausible sample tasks for demos and manual testing. The output is
+/// deterministic for a given `now`: titles are unique and numbered, and categories,
+/// statuses, priorities and dates are spread out over the past two months.
+pub fn sample_tasks(count: usize, now: DateTime<Utc>) -> Vec<Task> {
+    let now = now.trunc_subsecs(0);
+    (0..count)
+        .map(|i| {
+            let action = ACTIONS[i % ACTIONS.len()];
+            let subject = SUBJECTS[i * 7 % SUBJECTS.len()];
+            let mut task = Task::new(
+                format!("{} {} #{}", action, subject, i + 1),
+                format!("{} the {} before it slips again.", action, subject),
+                Category(CATEGORIES[i * 3 % CATEGORIES.len()].to_string()),
+            );
+            task.creation_date =
+                now - Duration::days((i * 13 % 60) as i64) - Duration::hours((i * 5 % 24) as i64);
+            task.priority = match i % 3 {
+                0 => Priority::Medium,
+                1 => Priority::High,
+                _ => Priority::Low,
+            };
+            if i % 2 == 0 {
+                task.due = Some(task.creation_date + Duration::days((i * 11 % 45) as i64 + 1));
+            }
+            if i % 4 == 1 {
+                task.label = Some(LABELS[i / 4 % LABELS.len()].to_string());
+            }
+            if i % 3 == 2 {
+                task.estimate_minutes = Some(15 * (i % 8 + 1) as u32);
+            }
+            if i % 4 == 3 {
+                task.status = TaskStatus::Done;
+                task.completed_at = Some(task.creation_date + Duration::hours(30));
+            }
+            task
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_sample_tasks() {
+        let now = Utc::now();
+        let tasks = sample_tasks(40, now);
+        assert_eq!(tasks.len(), 40);
+
+        let titles: HashSet<&str> = tasks.iter().map(|task| task.title.as_str()).collect();
+        assert_eq!(titles.len(), 40);
+        let categories: HashSet<&str> = tasks.iter().map(|task| task.category.0.as_str()).collect();
+        assert_eq!(categories.len(), CATEGORIES.len());
+        assert!(tasks.iter().any(|task| task.status == TaskStatus::Done));
+        assert!(tasks.iter().any(|task| task.status == TaskStatus::Active));
+        assert!(tasks.iter().all(|task| task.creation_date <= now));
+
+        assert_eq!(sample_tasks(40, now), tasks);
+        assert!(sample_tasks(0, now).is_empty());
+    }
+}
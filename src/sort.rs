@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+
+use crate::task::{Task, TaskStatus};
+
+/// A key tasks can be ordered by. Keys chain: later keys only break ties left by
+/// earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortKey {
+    /// Taskwarrior-style score from priority, due date and age, most urgent first
+    Urgency,
+    /// Active tasks before done ones
+    Status,
+    /// Earliest due date first; tasks without one last
+    Due,
+    /// Oldest first
+    Created,
+    Title,
+}
+
+impl SortKey {
+    pub fn cmp(self, a: &Task, b: &Task, now: DateTime<Utc>) -> Ordering {
+        match self {
+            SortKey::Urgency => b.urgency_at(now).total_cmp(&a.urgency_at(now)),
+            SortKey::Status => is_done(a).cmp(&is_done(b)),
+            SortKey::Due => match (a.due, b.due) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            SortKey::Created => a.creation_date.cmp(&b.creation_date),
+            SortKey::Title => a.title.cmp(&b.title),
+        }
+    }
+}
+
+fn is_done(task: &Task) -> bool {
+    task.status == TaskStatus::Done
+}
+
+/// Compares by each of `keys` in turn, then by creation date and title, so tasks
+/// always come out in the same order whatever order they went in.
+pub fn compare(keys: &[SortKey], a: &Task, b: &Task, now: DateTime<Utc>) -> Ordering {
+    keys.iter()
+        .chain(&[SortKey::Created, SortKey::Title])
+        .map(|key| key.cmp(a, b, now))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Sorts by `keys` (see `compare`). With `done_last`, done tasks go after all active
+/// ones regardless of the keys.
+pub fn sort_tasks(tasks: &mut [&Task], keys: &[SortKey], done_last: bool) {
+    let keys: Vec<SortKey> = done_last
+        .then_some(SortKey::Status)
+        .into_iter()
+        .chain(keys.iter().copied())
+        .collect();
+    let now = Utc::now();
+    tasks.sort_by(|a, b| compare(&keys, a, b, now));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Category, Priority};
+    use chrono::Duration;
+
+    fn task(title: &str, days_old: i64, done: bool) -> Task {
+        let mut task = Task::new(
+            title.to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        task.creation_date = Utc::now() - Duration::days(days_old);
+        if done {
+            task.status = TaskStatus::Done;
+        }
+        task
+    }
+
+    fn titles(tasks: &[&Task]) -> Vec<String> {
+        tasks.iter().map(|task| task.title.clone()).collect()
+    }
+
+    #[test]
+    fn test_status_sort_is_stable() {
+        let tasks = [
+            task("New done", 1, true),
+            task("Old active", 5, false),
+            task("Old done", 9, true),
+            task("New active", 2, false),
+            task("Same age b", 3, false),
+        ];
+        let mut same_age = task("Same age a", 0, false);
+        same_age.creation_date = tasks[4].creation_date;
+        let expected = [
+            "Old active",
+            "Same age a",
+            "Same age b",
+            "New active",
+            "Old done",
+            "New done",
+        ];
+        let mut refs: Vec<&Task> = tasks.iter().chain([&same_age]).collect();
+        for _ in 0..refs.len() {
+            refs.rotate_left(1);
+            let mut sorted = refs.clone();
+            sort_tasks(&mut sorted, &[SortKey::Status], false);
+            assert_eq!(titles(&sorted), expected);
+        }
+    }
+
+    #[test]
+    fn test_done_last_and_chained_keys() {
+        let mut urgent_done = task("Urgent done", 1, true);
+        urgent_done.priority = Priority::High;
+        urgent_done.due = Some(Utc::now() - Duration::days(1));
+        let mut due_soon = task("Due soon", 1, false);
+        due_soon.due = Some(Utc::now() + Duration::days(1));
+        let mut due_later = task("Due later", 1, false);
+        due_later.due = Some(Utc::now() + Duration::days(5));
+        let no_due = task("No due", 3, false);
+
+        let mut tasks = vec![&no_due, &urgent_done, &due_later, &due_soon];
+        sort_tasks(&mut tasks, &[SortKey::Urgency], false);
+        assert_eq!(titles(&tasks)[0], "Urgent done");
+        sort_tasks(&mut tasks, &[SortKey::Urgency], true);
+        assert_eq!(titles(&tasks)[3], "Urgent done");
+
+        sort_tasks(&mut tasks, &[SortKey::Due], false);
+        assert_eq!(
+            titles(&tasks),
+            ["Urgent done", "Due soon", "Due later", "No due"]
+        );
+        sort_tasks(&mut tasks, &[SortKey::Due], true);
+        assert_eq!(
+            titles(&tasks),
+            ["Due soon", "Due later", "No due", "Urgent done"]
+        );
+        sort_tasks(&mut tasks, &[SortKey::Title], false);
+        assert_eq!(
+            titles(&tasks),
+            ["Due later", "Due soon", "No due", "Urgent done"]
+        );
+    }
+}
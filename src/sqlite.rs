@@ -0,0 +1,161 @@
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::storage::Storage;
+use crate::task::Task;
+
+/// Schema migrations, applied in order. `PRAGMA user_version` records how many have
+/// run, so opening a database only applies the ones it hasn't seen yet.
+const MIGRATIONS: [&str; 1] = ["CREATE TABLE tasks (
+        title TEXT PRIMARY KEY NOT NULL,
+        status TEXT NOT NULL,
+        category TEXT NOT NULL,
+        due TEXT,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX tasks_status ON tasks (status);"];
+
+/// SQLite-backed storage: one row per task, holding the task as JSON next to a few
+/// columns for ad-hoc queries. Saves only touch the rows of tasks that changed.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Connection,
+    /// Tasks as of the last load or save, used to work out which rows to write.
+    persisted: HashMap<String, Task>,
+}
+
+impl SqliteStorage {
+    /// Opens (or creates) the database at `path` and brings its schema up to date.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let mut conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        migrate(&mut conn).map_err(|e| format!("Failed to migrate {}: {}", path.display(), e))?;
+        Ok(SqliteStorage {
+            conn,
+            persisted: HashMap::new(),
+        })
+    }
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let applied: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let tx = conn.transaction()?;
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", version + 1)?;
+    }
+    tx.commit()
+}
+
+impl Storage for SqliteStorage {
+    fn load(&mut self) -> Result<HashMap<String, Task>, String> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data FROM tasks")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        let mut tasks = HashMap::new();
+        for data in rows {
+            let data = data.map_err(|e| e.to_string())?;
+            let task: Task = serde_json::from_str(&data)
+                .map_err(|e| format!("Invalid task in database: {}", e))?;
+            tasks.insert(task.title.clone(), task);
+        }
+        self.persisted = tasks.clone();
+        Ok(tasks)
+    }
+
+    /// Upserts changed tasks and deletes removed ones in a single transaction.
+    fn save(&mut self, tasks: &HashMap<String, Task>) -> Result<(), String> {
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+        for (title, task) in tasks {
+            if self.persisted.get(title) == Some(task) {
+                continue;
+            }
+            let data = serde_json::to_string(task).map_err(|e| e.to_string())?;
+            tx.execute(
+                "INSERT INTO tasks (title, status, category, due, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (title) DO UPDATE SET
+                    status = excluded.status,
+                    category = excluded.category,
+                    due = excluded.due,
+                    data = excluded.data",
+                params![
+                    title,
+                    task.status.to_string(),
+                    task.category.0,
+                    task.due.map(|due| due.to_rfc3339()),
+                    data
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        for title in self.persisted.keys().filter(|t| !tasks.contains_key(*t)) {
+            tx.execute("DELETE FROM tasks WHERE title = ?1", params![title])
+                .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        self.persisted = tasks.clone();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Category, TaskStatus};
+    use crate::test_support::{cleanup_file, get_unique_file_path};
+    use crate::todo_list::{StoreFormat, TodoList};
+
+    #[test]
+    fn test_sqlite_round_trip() {
+        let file_path = get_unique_file_path().with_extension("db");
+        let mut todo_list = TodoList::with_format(file_path.clone(), StoreFormat::Sqlite).unwrap();
+        for title in ["Task 1", "Task 2", "Task 3"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        todo_list.mark_as_done("Task 1").unwrap();
+        todo_list.delete_task("Task 2").unwrap();
+
+        let reloaded = TodoList::with_format(file_path.clone(), StoreFormat::Sqlite).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(
+            reloaded.get_task("Task 1").unwrap().status,
+            TaskStatus::Done
+        );
+        assert!(reloaded.get_task("Task 2").is_none());
+
+        let conn = Connection::open(&file_path).unwrap();
+        let status: String = conn
+            .query_row(
+                "SELECT status FROM tasks WHERE title = 'Task 1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(status, "done");
+        let version: usize = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_sqlite_open_error() {
+        let file_path = get_unique_file_path().with_extension("db");
+        std::fs::write(&file_path, "not a database, just some text ".repeat(10)).unwrap();
+        let err = TodoList::with_format(file_path.clone(), StoreFormat::Sqlite).unwrap_err();
+        assert!(err.starts_with("Failed to migrate"), "{}", err);
+        cleanup_file(&file_path);
+    }
+}
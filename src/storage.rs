@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::task::Task;
+use crate::todo_list::STDIO_PATH;
+
+/// Version written in the envelope of JSON task files. Bump it whenever the
+/// on-disk schema changes incompatibly, and teach `StoredTasks` to migrate.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A backend a `TodoList` is loaded from and saved back to. `TodoList` keeps the
+/// tasks in memory and hands the whole map to `save` after every change, so a
+/// backend is free to rewrite everything or to write only what changed.
+pub trait Storage: Debug {
+    fn load(&mut self) -> Result<HashMap<String, Task>, String>;
+    fn save(&mut self, tasks: &HashMap<String, Task>) -> Result<(), String>;
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    version: u32,
+    tasks: BTreeMap<&'a String, &'a Task>,
+}
+
+/// Accepted layouts of a JSON task file: the versioned envelope, or the bare
+/// title-to-task map written before versioning (version 0).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredTasks {
+    Versioned {
+        version: u32,
+        /// Only parsed once `version` is known to be supported.
+        tasks: serde_json::Value,
+    },
+    Legacy(HashMap<String, Task>),
+}
+
+impl StoredTasks {
+    /// Fails for files written by a newer version, rather than misreading them.
+    fn into_tasks(self) -> Result<HashMap<String, Task>, String> {
+        match self {
+            StoredTasks::Versioned { version, .. } if version > SCHEMA_VERSION => Err(format!(
+                "Task file has schema version {}, but this version of todo only supports up to {}",
+                version, SCHEMA_VERSION
+            )),
+            StoredTasks::Versioned { tasks, .. } => {
+                serde_json::from_value(tasks).map_err(|e| format!("Invalid task list: {}", e))
+            }
+            StoredTasks::Legacy(tasks) => Ok(tasks),
+        }
+    }
+}
+
+/// Parses a JSON task list. Empty input is an empty list.
+pub(crate) fn read_tasks(mut reader: impl Read) -> Result<HashMap<String, Task>, String> {
+    let mut content = String::new();
+    reader
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read tasks: {}", e))?;
+    if content.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    serde_json::from_str::<StoredTasks>(&content)
+        .map_err(|e| format!("Invalid task list: {}", e))?
+        .into_tasks()
+}
+
+/// Writes tasks as pretty-printed JSON, sorted by title so it diffs cleanly, inside
+/// a `{"version", "tasks"}` envelope.
+pub(crate) fn write_tasks(
+    tasks: &HashMap<String, Task>,
+    mut writer: impl Write,
+) -> Result<(), String> {
+    let envelope = Envelope {
+        version: SCHEMA_VERSION,
+        tasks: tasks.iter().collect(),
+    };
+    serde_json::to_writer_pretty(&mut writer, &envelope)
+        .map_err(|e| format!("Failed to write tasks: {}", e))?;
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write tasks: {}", e))
+}
+
+/// A temp file to write next to `path` before renaming it into place
+/// (`tasks.json` -> `tasks.json.<pid>.<n>.tmp`). The pid and a per-process counter
+/// keep concurrent saves, from other processes or other threads, off each other's
+/// temp files.
+pub(crate) fn temp_path_for(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.{}.tmp", std::process::id(), n));
+    path.with_file_name(name)
+}
+
+/// A single JSON file, rewritten in full on every save via a temp file and rename.
+/// The path `-` reads the list from stdin and writes saves to stdout instead.
+#[derive(Debug)]
+pub struct JsonFileStorage {
+    path: PathBuf,
+}
+
+impl JsonFileStorage {
+    pub fn new(path: PathBuf) -> Self {
+        JsonFileStorage { path }
+    }
+}
+
+impl Storage for JsonFileStorage {
+    fn load(&mut self) -> Result<HashMap<String, Task>, String> {
+        if self.path == Path::new(STDIO_PATH) {
+            read_tasks(io::stdin().lock())
+        } else if self.path.exists() {
+            let file = fs::File::open(&self.path)
+                .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+            read_tasks(file)
+        } else {
+            Ok(HashMap::new())
+        }
+    }
+
+    fn save(&mut self, tasks: &HashMap<String, Task>) -> Result<(), String> {
+        if self.path == Path::new(STDIO_PATH) {
+            return write_tasks(tasks, io::stdout().lock());
+        }
+        let tmp_path = temp_path_for(&self.path);
+        let tmp_file = fs::File::create(&tmp_path)
+            .map_err(|e| format!("Failed to write to temp file: {}", e))?;
+        if let Err(e) = write_tasks(tasks, io::BufWriter::new(tmp_file)) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+        fs::rename(&tmp_path, &self.path).map_err(|e| format!("Failed to rename temp file: {}", e))
+    }
+}
+
+/// Keeps tasks in memory only, for tests and throwaway lists. Clones share the same
+/// tasks, so a clone kept outside the `TodoList` can inspect what was saved.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    tasks: Arc<Mutex<HashMap<String, Task>>>,
+}
+
+impl MemoryStorage {
+    pub fn new(tasks: HashMap<String, Task>) -> Self {
+        MemoryStorage {
+            tasks: Arc::new(Mutex::new(tasks)),
+        }
+    }
+
+    /// The tasks as of the last save.
+    pub fn saved(&self) -> HashMap<String, Task> {
+        self.tasks.lock().unwrap().clone()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&mut self) -> Result<HashMap<String, Task>, String> {
+        Ok(self.saved())
+    }
+
+    fn save(&mut self, tasks: &HashMap<String, Task>) -> Result<(), String> {
+        *self.tasks.lock().unwrap() = tasks.clone();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Category;
+    use crate::test_support::{cleanup_file, get_unique_file_path};
+    use std::thread;
+
+    #[test]
+    fn test_temp_paths_are_unique() {
+        let path = PathBuf::from("dir").join("tasks.json");
+        let first = temp_path_for(&path);
+        let second = temp_path_for(&path);
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), path.parent());
+        assert!(first
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("tasks.json."));
+    }
+
+    #[test]
+    fn test_concurrent_saves_do_not_collide() {
+        let file_path = get_unique_file_path();
+        let writers: Vec<_> = (0..4)
+            .map(|i| {
+                let file_path = file_path.clone();
+                thread::spawn(move || {
+                    let title = format!("Task {}", i);
+                    let task = Task::new(title.clone(), String::new(), Category("Test".into()));
+                    let tasks = HashMap::from([(title, task)]);
+                    let mut storage = JsonFileStorage::new(file_path);
+                    for _ in 0..20 {
+                        storage.save(&tasks).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let tasks = JsonFileStorage::new(file_path.clone()).load().unwrap();
+        assert_eq!(tasks.len(), 1);
+        let prefix = format!("{}.", file_path.display());
+        let leftovers = fs::read_dir(".")
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with(&prefix)
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+        cleanup_file(&file_path);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::template::truncate_str;
+
+/// Narrowest a column is shrunk to when a table has to fit a width.
+const MIN_SHRUNK_WIDTH: usize = 5;
+
+/// The characters a table's borders are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Box-drawing characters (`┌─┬─┐`)
+    Unicode,
+    /// Plain `+`, `-` and `|`, for terminals and fonts without box drawing
+    Ascii,
+}
+
+struct Borders {
+    horizontal: char,
+    vertical: char,
+    /// Left, middle and right corners of the top, header separator and bottom lines.
+    top: [char; 3],
+    middle: [char; 3],
+    bottom: [char; 3],
+}
+
+impl TableStyle {
+    fn borders(self) -> Borders {
+        match self {
+            TableStyle::Unicode => Borders {
+                horizontal: '─',
+                vertical: '│',
+                top: ['┌', '┬', '┐'],
+                middle: ['├', '┼', '┤'],
+                bottom: ['└', '┴', '┘'],
+            },
+            TableStyle::Ascii => Borders {
+                horizontal: '-',
+                vertical: '|',
+                top: ['+'; 3],
+                middle: ['+'; 3],
+                bottom: ['+'; 3],
+            },
+        }
+    }
+}
+
+/// Renders `rows` as a bordered table under a header row, padding each column to its
+/// widest cell. Line breaks inside cells become spaces. If `max_width` is given and
+/// the table is wider, the `shrink` column is cut down (to no less than a few
+/// characters) and its cells truncated with `…`. Widths are counted in characters.
+pub fn render_table(
+    headers: &[&str],
+    rows: &[Vec<String>],
+    style: TableStyle,
+    max_width: Option<usize>,
+    shrink: Option<usize>,
+) -> String {
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.replace(['\r', '\n'], " "))
+                .collect()
+        })
+        .collect();
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    // Each column adds a border and a space either side; the last border closes the row.
+    let total = widths.iter().map(|w| w + 3).sum::<usize>() + 1;
+    if let (Some(max_width), Some(column)) = (max_width, shrink) {
+        if let Some(width) = widths.get_mut(column) {
+            let excess = total.saturating_sub(max_width);
+            *width = width
+                .saturating_sub(excess)
+                .max(MIN_SHRUNK_WIDTH.min(*width));
+        }
+    }
+
+    let borders = style.borders();
+    let line = |[left, middle, right]: [char; 3]| {
+        let segments: Vec<String> = widths
+            .iter()
+            .map(|w| borders.horizontal.to_string().repeat(w + 2))
+            .collect();
+        format!("{}{}{}\n", left, segments.join(&middle.to_string()), right)
+    };
+    let row = |cells: Vec<String>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| {
+                let cell = truncate_str(cell, width);
+                let padding = width - cell.chars().count();
+                format!(" {}{} ", cell, " ".repeat(padding))
+            })
+            .collect();
+        let vertical = borders.vertical.to_string();
+        format!("{}{}{}\n", vertical, cells.join(&vertical), vertical)
+    };
+
+    let mut out = line(borders.top);
+    out.push_str(&row(headers.iter().map(|h| h.to_string()).collect()));
+    out.push_str(&line(borders.middle));
+    for cells in rows {
+        out.push_str(&row(cells));
+    }
+    out.push_str(&line(borders.bottom));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<Vec<String>> {
+        vec![
+            vec!["Buy milk".into(), "From the\ncorner shop".into()],
+            vec!["Café".into(), String::new()],
+        ]
+    }
+
+    #[test]
+    fn test_render_table() {
+        let table = render_table(
+            &["Title", "Description"],
+            &rows(),
+            TableStyle::Unicode,
+            None,
+            None,
+        );
+        assert_eq!(
+            table,
+            "┌──────────┬──────────────────────┐\n\
+             │ Title    │ Description          │\n\
+             ├──────────┼──────────────────────┤\n\
+             │ Buy milk │ From the corner shop │\n\
+             │ Café     │                      │\n\
+             └──────────┴──────────────────────┘\n"
+        );
+    }
+
+    #[test]
+    fn test_render_table_fits_width() {
+        let table = render_table(
+            &["Title", "Description"],
+            &rows(),
+            TableStyle::Ascii,
+            Some(25),
+            Some(1),
+        );
+        assert_eq!(
+            table,
+            "+----------+------------+\n\
+             | Title    | Descripti… |\n\
+             +----------+------------+\n\
+             | Buy milk | From the … |\n\
+             | Café     |            |\n\
+             +----------+------------+\n"
+        );
+        assert!(table.lines().all(|line| line.chars().count() <= 25));
+
+        let narrow = render_table(
+            &["Title", "Description"],
+            &rows(),
+            TableStyle::Ascii,
+            Some(10),
+            Some(1),
+        );
+        assert!(narrow.contains("| From… |"), "{}", narrow);
+    }
+}
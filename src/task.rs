@@ -0,0 +1,556 @@
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::recurrence::Recurrence;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TaskStatus {
+    Active,
+    Done,
+}
+
+impl std::fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskStatus::Active => write!(f, "on"),
+            TaskStatus::Done => write!(f, "done"),
+        }
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    /// Accepts `on`, `active` or `a` for active tasks and `done` or `d` for
+    /// finished ones, case-insensitively. Used by both `Select` and `Update`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "on" | "active" | "a" => Ok(TaskStatus::Active),
+            "done" | "d" => Ok(TaskStatus::Done),
+            _ => Err(format!("Invalid status: {}", s)),
+        }
+    }
+}
+
+/// Urgency contributed by each priority level.
+pub const URGENCY_PRIORITY_HIGH: f64 = 6.0;
+pub const URGENCY_PRIORITY_MEDIUM: f64 = 3.9;
+pub const URGENCY_PRIORITY_LOW: f64 = 1.8;
+/// Weight of due-date proximity; scales a factor from 0.2 (due in two weeks or
+/// more) to 1.0 (a week or more overdue). Tasks without a due date get nothing.
+pub const URGENCY_DUE: f64 = 12.0;
+/// Weight of age; scales a factor growing linearly to 1.0 over `URGENCY_AGE_MAX_DAYS`.
+pub const URGENCY_AGE: f64 = 2.0;
+pub const URGENCY_AGE_MAX_DAYS: f64 = 365.0;
+
+/// Ordered from least to most urgent, so `Priority::High > Priority::Low`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::Low => write!(f, "low"),
+            Priority::Medium => write!(f, "medium"),
+            Priority::High => write!(f, "high"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    /// Accepts `low`/`l`, `medium`/`med`/`m` and `high`/`h`, case-insensitively.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" | "l" => Ok(Priority::Low),
+            "medium" | "med" | "m" => Ok(Priority::Medium),
+            "high" | "h" => Ok(Priority::High),
+            _ => Err(format!("Invalid priority: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Category(pub String);
+
+impl std::fmt::Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Category {
+    pub fn to_lowercase(&self) -> Category {
+        Category(self.0.to_lowercase())
+    }
+}
+
+/// Parses a category name, trimming surrounding whitespace. Blank names are rejected.
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        if name.is_empty() {
+            return Err("Category name cannot be empty".to_string());
+        }
+        Ok(Category(name.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    pub title: String,
+    pub description: String,
+    #[serde(deserialize_with = "crate::date::deserialize_utc")]
+    pub creation_date: DateTime<Utc>,
+    pub category: Category,
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default, deserialize_with = "crate::date::deserialize_opt_utc")]
+    pub due: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    #[serde(default, deserialize_with = "crate::date::deserialize_opt_utc")]
+    pub remind_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub estimate_minutes: Option<u32>,
+    #[serde(default)]
+    pub spent_minutes: Option<u32>,
+    /// Set by every `TodoList` mutation; missing for tasks saved before it existed.
+    #[serde(default, deserialize_with = "crate::date::deserialize_opt_utc")]
+    pub last_modified: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "crate::date::deserialize_opt_utc")]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+    /// Who added the task; empty for tasks saved before it was recorded.
+    #[serde(default)]
+    pub created_by: String,
+    /// Repeats from `due`; has no effect on a task without a due date.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// File holding the full description, relative to the list's directory; the
+    /// `description` field then only keeps its first line. See
+    /// `TodoList::externalize_description`.
+    #[serde(default)]
+    pub description_file: Option<PathBuf>,
+    /// Paths or URLs of documents linked to the task, in the order they were attached.
+    #[serde(default)]
+    pub attachments: Vec<String>,
+}
+
+/// A partial update to a task: only the fields that are `Some` are changed. It
+/// deserializes from a JSON object holding any subset of the fields.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskPatch {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    #[serde(deserialize_with = "crate::date::deserialize_opt_utc")]
+    pub creation_date: Option<DateTime<Utc>>,
+    pub category: Option<Category>,
+    pub status: Option<TaskStatus>,
+    #[serde(deserialize_with = "crate::date::deserialize_opt_utc")]
+    pub due: Option<DateTime<Utc>>,
+    pub label: Option<String>,
+    pub priority: Option<Priority>,
+    pub assignee: Option<String>,
+}
+
+impl TaskPatch {
+    pub fn is_empty(&self) -> bool {
+        self == &TaskPatch::default()
+    }
+
+    /// Returns a copy of `task` with the patched fields replaced.
+    pub fn apply(self, task: &Task) -> Task {
+        let task = task.clone();
+        Task {
+            title: self.title.unwrap_or(task.title),
+            description: self.description.unwrap_or(task.description),
+            creation_date: self.creation_date.unwrap_or(task.creation_date),
+            category: self.category.unwrap_or(task.category),
+            status: self.status.unwrap_or(task.status),
+            due: self.due.or(task.due),
+            label: self.label.or(task.label),
+            priority: self.priority.unwrap_or(task.priority),
+            assignee: self.assignee.or(task.assignee),
+            ..task
+        }
+    }
+}
+
+/// Builds a `Task` field by field; see `Task::builder`. Optional fields take either a
+/// value or an `Option`, so `.due(date)` and `.due(maybe_date)` both work.
+#[derive(Debug, Clone)]
+pub struct TaskBuilder {
+    task: Task,
+}
+
+impl TaskBuilder {
+    pub fn creation_date(mut self, date: DateTime<Utc>) -> Self {
+        self.task.creation_date = date;
+        self
+    }
+
+    pub fn status(mut self, status: TaskStatus) -> Self {
+        self.task.status = status;
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.task.priority = priority;
+        self
+    }
+
+    pub fn depends_on(mut self, titles: Vec<String>) -> Self {
+        self.task.depends_on = titles;
+        self
+    }
+
+    pub fn due(mut self, due: impl Into<Option<DateTime<Utc>>>) -> Self {
+        self.task.due = due.into();
+        self
+    }
+
+    pub fn label(mut self, label: impl Into<Option<String>>) -> Self {
+        self.task.label = label.into();
+        self
+    }
+
+    pub fn assignee(mut self, assignee: impl Into<Option<String>>) -> Self {
+        self.task.assignee = assignee.into();
+        self
+    }
+
+    pub fn estimate_minutes(mut self, minutes: impl Into<Option<u32>>) -> Self {
+        self.task.estimate_minutes = minutes.into();
+        self
+    }
+
+    pub fn created_by(mut self, user: String) -> Self {
+        self.task.created_by = user;
+        self
+    }
+
+    pub fn recurrence(mut self, recurrence: impl Into<Option<Recurrence>>) -> Self {
+        self.task.recurrence = recurrence.into();
+        self
+    }
+
+    /// The task, with every field that wasn't set as in `Task::new`.
+    pub fn build(self) -> Task {
+        self.task
+    }
+}
+
+/// A checklist item within a task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subtask {
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// A timestamped progress note appended to a task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    pub text: String,
+    #[serde(deserialize_with = "crate::date::deserialize_utc")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Task {
+    pub fn new(title: String, description: String, category: Category) -> Self {
+        Task {
+            title,
+            description,
+            creation_date: Utc::now(),
+            category,
+            status: TaskStatus::Active,
+            depends_on: Vec::new(),
+            due: None,
+            notes: Vec::new(),
+            remind_at: None,
+            label: None,
+            priority: Priority::Medium,
+            estimate_minutes: None,
+            spent_minutes: None,
+            last_modified: None,
+            completed_at: None,
+            assignee: None,
+            subtasks: Vec::new(),
+            created_by: String::new(),
+            recurrence: None,
+            description_file: None,
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Starts building a task with the other fields defaulted as in `Task::new`.
+    pub fn builder(title: String, description: String, category: Category) -> TaskBuilder {
+        TaskBuilder {
+            task: Task::new(title, description, category),
+        }
+    }
+
+    /// Records that the task was just changed.
+    pub fn touch(&mut self) {
+        self.last_modified = Some(Utc::now());
+    }
+
+    /// Marks the task done, recording when it was completed.
+    pub fn complete(&mut self) {
+        self.status = TaskStatus::Done;
+        self.completed_at = Some(Utc::now());
+        self.touch();
+    }
+
+    /// Taskwarrior-style urgency: a weighted sum of priority, due-date proximity and
+    /// age. Higher is more urgent.
+    pub fn urgency(&self) -> f64 {
+        self.urgency_at(Utc::now())
+    }
+
+    pub fn urgency_at(&self, now: DateTime<Utc>) -> f64 {
+        const SECONDS_PER_DAY: f64 = 86_400.0;
+        let priority = match self.priority {
+            Priority::High => URGENCY_PRIORITY_HIGH,
+            Priority::Medium => URGENCY_PRIORITY_MEDIUM,
+            Priority::Low => URGENCY_PRIORITY_LOW,
+        };
+        let due = match self.due {
+            Some(due) => {
+                let days_overdue = (now - due).num_seconds() as f64 / SECONDS_PER_DAY;
+                if days_overdue >= 7.0 {
+                    1.0
+                } else if days_overdue >= -14.0 {
+                    (days_overdue + 14.0) * 0.8 / 21.0 + 0.2
+                } else {
+                    0.2
+                }
+            }
+            None => 0.0,
+        };
+        let age_days = (now - self.creation_date).num_seconds() as f64 / SECONDS_PER_DAY;
+        let age = (age_days / URGENCY_AGE_MAX_DAYS).clamp(0.0, 1.0);
+        priority + URGENCY_DUE * due + URGENCY_AGE * age
+    }
+
+    /// Returns `(done, total)` subtask counts.
+    pub fn subtask_counts(&self) -> (usize, usize) {
+        let done = self.subtasks.iter().filter(|subtask| subtask.done).count();
+        (done, self.subtasks.len())
+    }
+
+    /// Percentage of subtasks done, rounded down, or `None` if the task has no subtasks.
+    pub fn progress_percent(&self) -> Option<u8> {
+        let (done, total) = self.subtask_counts();
+        (total > 0).then(|| (done * 100 / total) as u8)
+    }
+
+    /// The next `n` due dates of a recurring task, counting from its due date.
+    /// Empty if the task doesn't recur or has no due date.
+    pub fn upcoming_occurrences(&self, n: usize) -> Vec<DateTime<Local>> {
+        self.upcoming_occurrences_after(Local::now(), n)
+    }
+
+    pub fn upcoming_occurrences_after(
+        &self,
+        now: DateTime<Local>,
+        n: usize,
+    ) -> Vec<DateTime<Local>> {
+        let (Some(recurrence), Some(due)) = (self.recurrence, self.due) else {
+            return Vec::new();
+        };
+        let anchor = due.with_timezone(&Local);
+        (0..)
+            .map_while(|k| recurrence.nth_after(&anchor, k))
+            .filter(|occurrence| *occurrence > now)
+            .take(n)
+            .collect()
+    }
+
+    /// When the task last changed, falling back to its creation date for
+    /// tasks that have never been modified since `last_modified` was added.
+    pub fn modified_at(&self) -> DateTime<Utc> {
+        self.last_modified.unwrap_or(self.creation_date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{cleanup_file, setup};
+
+    #[test]
+    fn test_category_fromstr() {
+        let (_todo_list, file_path) = setup();
+        let category: Category = "TestCategory".parse().unwrap();
+        assert_eq!(category.0, "TestCategory");
+        let category: Category = "  Work \n".parse().unwrap();
+        assert_eq!(category.0, "Work");
+        assert_eq!(category.to_lowercase().0, "work");
+        assert!("   ".parse::<Category>().is_err());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_taskstatus_fromstr() {
+        let (_todo_list, file_path) = setup();
+        assert_eq!("on".parse::<TaskStatus>().unwrap(), TaskStatus::Active);
+        assert_eq!("done".parse::<TaskStatus>().unwrap(), TaskStatus::Done);
+        assert!("invalid".parse::<TaskStatus>().is_err());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_progress_percent() {
+        let mut task = Task::new(
+            "Test".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        assert_eq!(task.subtask_counts(), (0, 0));
+        assert_eq!(task.progress_percent(), None);
+
+        for (text, done) in [("a", true), ("b", false), ("c", false)] {
+            task.subtasks.push(Subtask {
+                text: text.to_string(),
+                done,
+            });
+        }
+        assert_eq!(task.subtask_counts(), (1, 3));
+        assert_eq!(task.progress_percent(), Some(33));
+
+        task.subtasks
+            .iter_mut()
+            .for_each(|subtask| subtask.done = true);
+        assert_eq!(task.progress_percent(), Some(100));
+    }
+
+    #[test]
+    fn test_urgency() {
+        let now = Utc::now();
+        let mut task = Task::new(
+            "Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        task.creation_date = now;
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+        assert!(close(task.urgency_at(now), URGENCY_PRIORITY_MEDIUM));
+
+        task.priority = Priority::High;
+        task.due = Some(now);
+        // Due now: (0 + 14) * 0.8 / 21 + 0.2 = 0.7333...
+        assert!(close(
+            task.urgency_at(now),
+            URGENCY_PRIORITY_HIGH + URGENCY_DUE * (14.0 * 0.8 / 21.0 + 0.2)
+        ));
+
+        task.priority = Priority::Low;
+        task.due = Some(now - chrono::Duration::days(10));
+        task.creation_date = now - chrono::Duration::days(730);
+        assert!(close(
+            task.urgency_at(now),
+            URGENCY_PRIORITY_LOW + URGENCY_DUE + URGENCY_AGE
+        ));
+
+        task.due = Some(now + chrono::Duration::days(30));
+        task.creation_date = now;
+        assert!(close(
+            task.urgency_at(now),
+            URGENCY_PRIORITY_LOW + URGENCY_DUE * 0.2
+        ));
+    }
+
+    #[test]
+    fn test_priority_fromstr_and_order() {
+        assert_eq!("High".parse::<Priority>().unwrap(), Priority::High);
+        assert_eq!("m".parse::<Priority>().unwrap(), Priority::Medium);
+        assert_eq!("low".parse::<Priority>().unwrap(), Priority::Low);
+        assert!("urgent".parse::<Priority>().is_err());
+        assert!(Priority::High > Priority::Medium && Priority::Medium > Priority::Low);
+        assert_eq!(Priority::default(), Priority::Medium);
+    }
+
+    #[test]
+    fn test_task_builder() {
+        let category = Category("work".to_string());
+        let plain = Task::builder("Plain".to_string(), String::new(), category.clone()).build();
+        assert_eq!(plain.priority, Priority::Medium);
+        assert_eq!(plain.status, TaskStatus::Active);
+        assert_eq!(plain.due, None);
+
+        let created = Utc::now() - chrono::Duration::days(1);
+        let due = created + chrono::Duration::days(7);
+        let task = Task::builder("Report".to_string(), "Weekly".to_string(), category)
+            .creation_date(created)
+            .status(TaskStatus::Done)
+            .priority(Priority::High)
+            .due(due)
+            .label(Some("red".to_string()))
+            .assignee(None)
+            .estimate_minutes(30)
+            .depends_on(vec!["Plain".to_string()])
+            .created_by("alice".to_string())
+            .recurrence("weekly".parse::<Recurrence>().unwrap())
+            .build();
+        assert_eq!(task.title, "Report");
+        assert_eq!(task.creation_date, created);
+        assert_eq!(task.status, TaskStatus::Done);
+        assert_eq!(task.priority, Priority::High);
+        assert_eq!(task.due, Some(due));
+        assert_eq!(task.label.as_deref(), Some("red"));
+        assert_eq!(task.assignee, None);
+        assert_eq!(task.estimate_minutes, Some(30));
+        assert_eq!(task.depends_on, ["Plain"]);
+        assert_eq!(task.created_by, "alice");
+        assert_eq!(task.recurrence.unwrap().to_string(), "weekly");
+        assert!(task.notes.is_empty() && task.subtasks.is_empty());
+    }
+
+    #[test]
+    fn test_upcoming_occurrences() {
+        let mut task = Task::new(
+            "Standup".to_string(),
+            "Daily sync".to_string(),
+            Category("work".to_string()),
+        );
+        let now = Local::now();
+        assert!(task.upcoming_occurrences_after(now, 3).is_empty());
+
+        task.recurrence = Some("weekly".parse().unwrap());
+        assert!(task.upcoming_occurrences_after(now, 3).is_empty());
+
+        let due = now - chrono::Duration::days(10);
+        task.due = Some(due.with_timezone(&Utc));
+        let upcoming = task.upcoming_occurrences_after(now, 3);
+        assert_eq!(upcoming.len(), 3);
+        assert!(upcoming[0] > now);
+        for (i, occurrence) in upcoming.iter().enumerate() {
+            let days = (occurrence.date_naive() - due.date_naive()).num_days();
+            assert_eq!(days, 7 * (i as i64 + 2));
+            assert_eq!(occurrence.time(), due.time());
+        }
+        assert!(task.upcoming_occurrences_after(now, 0).is_empty());
+    }
+}
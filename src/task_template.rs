@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::storage::temp_path_for;
+use crate::task::Priority;
+
+/// The fields a new task can be stamped out from with `add --template`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskTemplate {
+    pub title: String,
+    pub description: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+}
+
+/// Values given on the command line, which take precedence over a template's.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateOverrides {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub priority: Option<Priority>,
+}
+
+impl TaskTemplate {
+    /// The fields for a new task: each override if given, otherwise the template's.
+    pub fn instantiate(&self, overrides: TemplateOverrides) -> TaskTemplate {
+        TaskTemplate {
+            title: overrides.title.unwrap_or_else(|| self.title.clone()),
+            description: overrides
+                .description
+                .unwrap_or_else(|| self.description.clone()),
+            category: overrides.category.or_else(|| self.category.clone()),
+            priority: overrides.priority.or(self.priority),
+        }
+    }
+}
+
+/// Named task templates, kept in a sidecar file next to the list
+/// (`tasks.json` -> `tasks.templates.json`).
+#[derive(Debug)]
+pub struct TemplateStore {
+    path: PathBuf,
+    templates: BTreeMap<String, TaskTemplate>,
+}
+
+impl TemplateStore {
+    pub fn path_for(list_path: &Path) -> PathBuf {
+        list_path.with_extension("templates.json")
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let templates = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid templates in {}: {}", path.display(), e))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(TemplateStore { path, templates })
+    }
+
+    /// Saves `template` under `name`, replacing any template already saved with that name.
+    pub fn set(&mut self, name: &str, template: TaskTemplate) {
+        self.templates.insert(name.to_string(), template);
+    }
+
+    pub fn get(&self, name: &str) -> Result<&TaskTemplate, String> {
+        match self.templates.get(name) {
+            Some(template) => Ok(template),
+            None if self.templates.is_empty() => {
+                Err(format!("No template named '{}' (none saved yet)", name))
+            }
+            None => Err(format!(
+                "No template named '{}'. Saved templates: {}",
+                name,
+                self.templates
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &TaskTemplate)> {
+        self.templates
+            .iter()
+            .map(|(name, template)| (name.as_str(), template))
+    }
+
+    /// Writes the templates via a temp file and rename, like `TodoList::save`.
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.templates)
+            .map_err(|e| format!("Failed to serialize templates: {}", e))?;
+        let tmp_path = temp_path_for(&self.path);
+        fs::write(&tmp_path, content)
+            .map_err(|e| format!("Failed to write to temp file: {}", e))?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| format!("Failed to rename temp file: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::cleanup_file;
+
+    fn weekly_report() -> TaskTemplate {
+        TaskTemplate {
+            title: "Weekly report".to_string(),
+            description: "Summarize the week".to_string(),
+            category: Some("work".to_string()),
+            priority: Some(Priority::High),
+        }
+    }
+
+    #[test]
+    fn test_instantiate_with_overrides() {
+        let template = weekly_report();
+        assert_eq!(template.instantiate(TemplateOverrides::default()), template);
+
+        let fields = template.instantiate(TemplateOverrides {
+            title: Some("Weekly report W12".to_string()),
+            priority: Some(Priority::Low),
+            ..TemplateOverrides::default()
+        });
+        assert_eq!(fields.title, "Weekly report W12");
+        assert_eq!(fields.description, "Summarize the week");
+        assert_eq!(fields.category.as_deref(), Some("work"));
+        assert_eq!(fields.priority, Some(Priority::Low));
+
+        let bare = TaskTemplate {
+            category: None,
+            priority: None,
+            ..weekly_report()
+        };
+        let fields = bare.instantiate(TemplateOverrides {
+            category: Some("home".to_string()),
+            ..TemplateOverrides::default()
+        });
+        assert_eq!(fields.category.as_deref(), Some("home"));
+        assert_eq!(fields.priority, None);
+    }
+
+    #[test]
+    fn test_template_store_roundtrip() {
+        let path = PathBuf::from("test_templates.templates.json");
+        cleanup_file(&path);
+        let mut store = TemplateStore::load(path.clone()).unwrap();
+        assert!(store.get("weekly").unwrap_err().contains("none saved"));
+        store.set("weekly", weekly_report());
+        store.save().unwrap();
+
+        let store = TemplateStore::load(path.clone()).unwrap();
+        assert_eq!(store.get("weekly").unwrap(), &weekly_report());
+        let missing = store.get("daily").unwrap_err();
+        assert!(missing.contains("'daily'") && missing.contains("weekly"));
+        assert_eq!(store.iter().count(), 1);
+        cleanup_file(&path);
+    }
+}
@@ -0,0 +1,93 @@
+use regex::Regex;
+
+use crate::date::format_date;
+use crate::task::Task;
+
+/// Substitutes `{field}` placeholders in `template` with values from `task`.
+/// Unknown placeholders are left untouched.
+pub fn render_template(template: &str, task: &Task) -> String {
+    let re = Regex::new(r"\{(\w+)\}").unwrap();
+    re.replace_all(template, |cap: &regex::Captures| match &cap[1] {
+        "title" => task.title.clone(),
+        "description" => task.description.clone(),
+        "status" => task.status.to_string(),
+        "category" => task.category.to_string(),
+        "date" => format_date(task.creation_date),
+        "due" => task.due.map(format_date).unwrap_or_default(),
+        _ => cap[0].to_string(),
+    })
+    .into_owned()
+}
+
+/// Expands `${VAR}` references from the process environment. Unset variables
+/// are left as the literal `${VAR}`.
+pub fn expand_env(s: &str) -> String {
+    let re = Regex::new(r"\$\{(\w+)\}").unwrap();
+    re.replace_all(s, |cap: &regex::Captures| {
+        std::env::var(&cap[1]).unwrap_or_else(|_| cap[0].to_string())
+    })
+    .into_owned()
+}
+
+/// Shortens `s` to at most `n` characters (not bytes), replacing the tail with `…`
+/// when anything had to be cut.
+pub fn truncate_str(s: &str, n: usize) -> String {
+    if s.chars().count() <= n {
+        return s.to_string();
+    }
+    let mut truncated: String = s.chars().take(n.saturating_sub(1)).collect();
+    if n > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Category;
+
+    #[test]
+    fn test_render_template() {
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Mentions {title}".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        assert_eq!(
+            render_template("{title} [{status}] {category}", &task),
+            "Test Task [on] TestCategory"
+        );
+        assert_eq!(
+            render_template("{description}|{due}|{unknown}", &task),
+            "Mentions {title}||{unknown}"
+        );
+    }
+
+    #[test]
+    fn test_truncate_str() {
+        assert_eq!(truncate_str("short", 10), "short");
+        assert_eq!(truncate_str("exactly", 7), "exactly");
+        assert_eq!(truncate_str("a longer description", 8), "a longe…");
+        assert_eq!(truncate_str("héllo wörld", 6), "héllo…");
+        assert_eq!(truncate_str("日本語のタスク", 3), "日本…");
+        assert_eq!(truncate_str("🦀🦀🦀", 2), "🦀…");
+        assert_eq!(truncate_str("abc", 1), "…");
+        assert_eq!(truncate_str("abc", 0), "");
+    }
+
+    #[test]
+    fn test_expand_env() {
+        std::env::set_var("TODO_TEST_PROJECT", "apollo");
+        std::env::remove_var("TODO_TEST_UNSET");
+        assert_eq!(expand_env("work/${TODO_TEST_PROJECT}"), "work/apollo");
+        assert_eq!(
+            expand_env("${TODO_TEST_UNSET} and ${TODO_TEST_PROJECT}"),
+            "${TODO_TEST_UNSET} and apollo"
+        );
+        assert_eq!(
+            expand_env("$TODO_TEST_PROJECT {x}"),
+            "$TODO_TEST_PROJECT {x}"
+        );
+    }
+}
@@ -0,0 +1,2754 @@
+use chrono::{DateTime, Local, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::archive::Archive;
+use crate::html::render_html;
+use crate::jsonl::JsonlJournal;
+use crate::predicate::{parse_predicates, Predicate};
+use crate::recurrence::Recurrence;
+#[cfg(feature = "sqlite")]
+use crate::sqlite::SqliteStorage;
+pub use crate::storage::SCHEMA_VERSION;
+use crate::storage::{read_tasks, write_tasks, JsonFileStorage, MemoryStorage, Storage};
+use crate::task::{Category, Note, Subtask, Task, TaskPatch, TaskStatus};
+use crate::template::truncate_str;
+use crate::validate::{normalize_title, validate_tasks, ValidationIssue};
+
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+/// Share of words two tasks must have in common for `find_similar` to flag them.
+pub const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.6;
+
+fn default_similarity_threshold() -> f64 {
+    DEFAULT_SIMILARITY_THRESHOLD
+}
+
+/// Lowercase words of a task's title and description.
+fn words(task: &Task) -> HashSet<String> {
+    format!("{} {}", task.title, task.description)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Words shared by both sets as a share of all their words (Jaccard index).
+fn word_overlap(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// How much of the first line of an external description stays in the list.
+const DESCRIPTION_SUMMARY_LEN: usize = 60;
+
+/// A file name for `title`'s external description: its letters and digits, with
+/// runs of anything else turned into `-`.
+fn description_file_name(title: &str) -> String {
+    let slug: String = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .take(40)
+        .collect();
+    if slug.is_empty() {
+        "task".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Path meaning "read the list from stdin and write saves to stdout".
+pub const STDIO_PATH: &str = "-";
+
+fn detached_storage() -> Box<dyn Storage> {
+    Box::new(MemoryStorage::default())
+}
+
+/// What to do when a task is added to a list that already has its title.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ConflictStrategy {
+    /// Abort with an error
+    Fail,
+    /// Leave both lists untouched
+    Skip,
+    /// Replace the existing task
+    Overwrite,
+    /// Keep both, giving the incoming task an unused title like "Title (2)"
+    Rename,
+}
+
+/// What `TodoList::merge` did with each incoming task, by title.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergeReport {
+    /// Tasks whose title was free.
+    pub added: Vec<String>,
+    /// Tasks that replaced an existing one (`Overwrite`).
+    pub overwritten: Vec<String>,
+    /// `(incoming, stored)` titles of tasks kept under a new title (`Rename`).
+    pub renamed: Vec<(String, String)>,
+    /// Tasks left out because the title was taken (`Skip`).
+    pub skipped: Vec<String>,
+}
+
+impl MergeReport {
+    /// How many incoming tasks had a title that was already taken.
+    pub fn conflicts(&self) -> usize {
+        self.overwritten.len() + self.renamed.len() + self.skipped.len()
+    }
+}
+
+/// Activity within a time window, from `TodoList::stats_since`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskStats {
+    /// Tasks created in the window.
+    pub created: usize,
+    /// Tasks completed in the window, whenever they were created.
+    pub completed: usize,
+    /// Tasks created in the window that are done by now.
+    pub created_and_done: usize,
+}
+
+impl TaskStats {
+    /// Whether nothing was created or completed in the window.
+    pub fn is_empty(&self) -> bool {
+        self.created == 0 && self.completed == 0
+    }
+
+    /// Share (0.0 to 1.0) of the tasks created in the window that are done, or `None`
+    /// if none were created.
+    pub fn completion_rate(&self) -> Option<f64> {
+        (self.created > 0).then(|| self.created_and_done as f64 / self.created as f64)
+    }
+}
+
+/// Field used to bucket tasks in grouped listings.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum GroupBy {
+    Category,
+    Status,
+}
+
+/// Buckets tasks by `field`. Groups iterate in sorted order and tasks within a
+/// group are sorted by title.
+pub fn group_tasks(tasks: Vec<&Task>, field: GroupBy) -> BTreeMap<String, Vec<&Task>> {
+    let mut groups: BTreeMap<String, Vec<&Task>> = BTreeMap::new();
+    for task in tasks {
+        let key = match field {
+            GroupBy::Category => task.category.to_string(),
+            GroupBy::Status => task.status.to_string(),
+        };
+        groups.entry(key).or_default().push(task);
+    }
+    for group in groups.values_mut() {
+        group.sort_by(|a, b| a.title.cmp(&b.title));
+    }
+    groups
+}
+
+/// On-disk layout of a task list.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum StoreFormat {
+    /// A single JSON object, rewritten on every change
+    #[default]
+    Json,
+    /// One JSON record per line, appended on every change
+    Jsonl,
+    /// A SQLite database, updating only the rows that changed
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+/// File extensions recognized as SQLite databases by `StoreFormat::for_path`.
+const SQLITE_EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+impl StoreFormat {
+    /// Picks the format implied by a file's extension: `.json` for `Json`, `.jsonl` for
+    /// `Jsonl`, `.db`, `.sqlite` or `.sqlite3` for `Sqlite`. Other extensions give `None`.
+    /// Fails for SQLite files when built without the `sqlite` feature.
+    pub fn for_path(path: &Path) -> Result<Option<StoreFormat>, String> {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("json") => Ok(Some(StoreFormat::Json)),
+            Some("jsonl") => Ok(Some(StoreFormat::Jsonl)),
+            #[cfg(feature = "sqlite")]
+            Some(ext) if SQLITE_EXTENSIONS.contains(&ext) => Ok(Some(StoreFormat::Sqlite)),
+            #[cfg(not(feature = "sqlite"))]
+            Some(ext) if SQLITE_EXTENSIONS.contains(&ext) => Err(format!(
+                "{} looks like a SQLite database, but this build lacks the `sqlite` feature",
+                path.display()
+            )),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TodoList {
+    tasks: HashMap<String, Task>,
+    file_path: PathBuf,
+    /// Lists deserialized from serde have nowhere to save to and keep changes in memory.
+    #[serde(skip, default = "detached_storage")]
+    storage: Box<dyn Storage>,
+    #[serde(skip)]
+    dry_run: bool,
+    #[serde(skip)]
+    backups: usize,
+    #[serde(skip, default = "default_similarity_threshold")]
+    similarity_threshold: f64,
+}
+
+impl TodoList {
+    pub fn new(file_path: PathBuf) -> Self {
+        let mut storage = JsonFileStorage::new(file_path.clone());
+        // An unreadable JSON file opens as an empty list, as it always has.
+        let tasks = storage.load().unwrap_or_default();
+        TodoList {
+            tasks,
+            file_path,
+            storage: Box::new(storage),
+            dry_run: false,
+            backups: 0,
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
+        }
+    }
+
+    /// Opens a list stored in the given format.
+    ///
+    /// `Json` rewrites the whole file on every change: simple, easy to read and diff,
+    /// but the cost of each write grows with the list. `Jsonl` appends one line per changed
+    /// task (or a tombstone per deleted one), which keeps writes cheap for high-volume
+    /// logging at the cost of replaying the whole journal on load. The journal is compacted
+    /// once it grows to several times the number of live tasks. `Sqlite` keeps one row per
+    /// task, so saves stay cheap however large the list grows.
+    ///
+    /// Unlike `new`, fails if the file exists but can't be read, such as a JSON file
+    /// written by a newer version, so it isn't overwritten with an empty list.
+    pub fn with_format(file_path: PathBuf, format: StoreFormat) -> Result<Self, String> {
+        let storage: Box<dyn Storage> = match format {
+            StoreFormat::Json => Box::new(JsonFileStorage::new(file_path.clone())),
+            StoreFormat::Jsonl => Box::new(JsonlJournal::new(&file_path)),
+            #[cfg(feature = "sqlite")]
+            StoreFormat::Sqlite => Box::new(SqliteStorage::open(&file_path)?),
+        };
+        TodoList::with_storage(file_path, storage)
+    }
+
+    /// Opens a list backed by any `Storage`, such as a `MemoryStorage` in tests.
+    /// `file_path` is still used for backups and sidecar files such as the archive.
+    pub fn with_storage(file_path: PathBuf, mut storage: Box<dyn Storage>) -> Result<Self, String> {
+        Ok(TodoList {
+            tasks: storage.load()?,
+            file_path,
+            storage,
+            dry_run: false,
+            backups: 0,
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
+        })
+    }
+
+    /// Reads a JSON list (versioned or legacy) from any reader. The list is not tied
+    /// to a file: like `--file -`, its saves are written to stdout.
+    pub fn from_reader(reader: impl Read) -> Result<Self, String> {
+        Ok(TodoList {
+            tasks: read_tasks(reader)?,
+            file_path: PathBuf::from(STDIO_PATH),
+            storage: Box::new(JsonFileStorage::new(PathBuf::from(STDIO_PATH))),
+            dry_run: false,
+            backups: 0,
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
+        })
+    }
+
+    /// Writes the list as pretty-printed JSON, sorted by title so it diffs cleanly,
+    /// inside a `{"version", "tasks"}` envelope.
+    pub fn to_writer(&self, writer: impl Write) -> Result<(), String> {
+        write_tasks(&self.tasks, writer)
+    }
+
+    /// In dry-run mode changes are validated and applied in memory but never written to disk.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Keeps the last `count` versions of the file as `<file>.1` (newest) to
+    /// `<file>.<count>`, rotated on every save. 0 disables backups.
+    pub fn set_backups(&mut self, count: usize) {
+        self.backups = count;
+    }
+
+    /// How much of their wording (0.0 to 1.0) two tasks must share for `find_similar`
+    /// to report them. Defaults to `DEFAULT_SIMILARITY_THRESHOLD`.
+    pub fn set_similarity_threshold(&mut self, threshold: f64) {
+        self.similarity_threshold = threshold;
+    }
+
+    /// Copies the list file to `dest`, or to a timestamped `<file>.<YYYYmmdd-HHMMSS>.bak`
+    /// next to it (or inside `dest` if that is a directory). Returns the backup's path.
+    pub fn backup(&self, dest: Option<&Path>) -> Result<PathBuf, String> {
+        if !self.file_path.exists() {
+            return Err(format!(
+                "Nothing to back up: {} does not exist",
+                self.file_path.display()
+            ));
+        }
+        let file_name = self
+            .file_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let stamped = format!("{}.{}.bak", file_name, Local::now().format("%Y%m%d-%H%M%S"));
+        let target = match dest {
+            Some(dir) if dir.is_dir() => dir.join(stamped),
+            Some(path) => path.to_path_buf(),
+            None => self.file_path.with_file_name(stamped),
+        };
+        if !self.dry_run {
+            fs::copy(&self.file_path, &target)
+                .map_err(|e| format!("Failed to write backup {}: {}", target.display(), e))?;
+        }
+        Ok(target)
+    }
+
+    /// Adds a task, trimming surrounding whitespace from its title first.
+    pub fn add_task(&mut self, task: Task) -> Result<(), String> {
+        self.add_unsaved(task)?;
+        self.save()?;
+        Ok(())
+    }
+
+    /// Adds each task like `add_task`, saving once at the end. A failure on one task
+    /// (such as a taken title) doesn't stop the others; each outcome is the added
+    /// title or an error. Only a failed save fails the whole call.
+    pub fn add_each(&mut self, tasks: Vec<Task>) -> Result<Vec<Result<String, String>>, String> {
+        let results: Vec<_> = tasks
+            .into_iter()
+            .map(|task| self.add_unsaved(task))
+            .collect();
+        if results.iter().any(Result::is_ok) {
+            self.save()?;
+        }
+        Ok(results)
+    }
+
+    fn add_unsaved(&mut self, mut task: Task) -> Result<String, String> {
+        task.title = task.title.trim().to_string();
+        if task.title.is_empty() {
+            Err("Task title cannot be empty".to_string())
+        } else if self.tasks.contains_key(&task.title) {
+            Err(format!("Task with title '{}' already exists", task.title))
+        } else if self.creates_cycle(&task, &task.title) {
+            Err(format!(
+                "Task '{}' would create a dependency cycle",
+                task.title
+            ))
+        } else {
+            let title = task.title.clone();
+            task.touch();
+            self.tasks.insert(title.clone(), task);
+            Ok(title)
+        }
+    }
+
+    /// Adds several tasks with a single save. Titles that are already taken get a
+    /// numeric suffix, as with `ConflictStrategy::Rename`.
+    pub fn add_tasks(&mut self, tasks: Vec<Task>) -> Result<usize, String> {
+        let count = tasks.len();
+        for mut task in tasks {
+            if self.tasks.contains_key(&task.title) {
+                task.title = self.unused_title(&task.title);
+            }
+            task.touch();
+            self.tasks.insert(task.title.clone(), task);
+        }
+        if count > 0 {
+            self.save()?;
+        }
+        Ok(count)
+    }
+
+    /// Like `add_task`, but replaces an existing task with the same title instead
+    /// of failing. With `keep_creation_date` the replaced task's creation date is
+    /// carried over. Returns whether an existing task was replaced.
+    pub fn upsert_task(
+        &mut self,
+        mut task: Task,
+        keep_creation_date: bool,
+    ) -> Result<bool, String> {
+        task.title = task.title.trim().to_string();
+        if task.title.is_empty() {
+            return Err("Task title cannot be empty".to_string());
+        }
+        if self.creates_cycle(&task, &task.title) {
+            return Err(format!(
+                "Task '{}' would create a dependency cycle",
+                task.title
+            ));
+        }
+        let replaced = match self.tasks.get(&task.title) {
+            Some(existing) => {
+                if keep_creation_date {
+                    task.creation_date = existing.creation_date;
+                }
+                true
+            }
+            None => false,
+        };
+        task.touch();
+        self.tasks.insert(task.title.clone(), task);
+        self.save()?;
+        Ok(replaced)
+    }
+
+    /// Marks a task done. `title` may be a unique prefix of the task's title;
+    /// see `resolve_title`.
+    pub fn mark_as_done(&mut self, title: &str) -> Result<(), String> {
+        self.complete_unsaved(title)?;
+        self.save()?;
+        Ok(())
+    }
+
+    /// Marks each title done like `mark_as_done`, saving once at the end. A failure on
+    /// one title doesn't stop the others; each outcome is the resolved title or an error.
+    pub fn mark_all_done(
+        &mut self,
+        titles: &[String],
+    ) -> Result<Vec<Result<String, String>>, String> {
+        let results: Vec<_> = titles
+            .iter()
+            .map(|title| self.complete_unsaved(title))
+            .collect();
+        if results.iter().any(Result::is_ok) {
+            self.save()?;
+        }
+        Ok(results)
+    }
+
+    fn complete_unsaved(&mut self, title: &str) -> Result<String, String> {
+        let title = self.resolve_title(title)?;
+        if let Some(task) = self.tasks.get_mut(&title) {
+            task.complete();
+        }
+        Ok(title)
+    }
+
+    /// Moves a done task back to Active and clears its completion time. Reopening a
+    /// task that is already active is an error, so typos don't go unnoticed.
+    pub fn reopen(&mut self, title: &str) -> Result<(), String> {
+        let title = self.resolve_title(title)?;
+        let task = self.tasks.get_mut(&title).expect("resolved title exists");
+        if task.status == TaskStatus::Active {
+            return Err(format!("Task '{}' is already active", title));
+        }
+        task.status = TaskStatus::Active;
+        task.completed_at = None;
+        task.touch();
+        self.save()?;
+        Ok(())
+    }
+
+    /// Marks a task as modified now without changing anything else, e.g. after
+    /// reviewing it.
+    pub fn touch(&mut self, title: &str) -> Result<(), String> {
+        let title = self.resolve_title(title)?;
+        self.tasks
+            .get_mut(&title)
+            .expect("resolved title exists")
+            .touch();
+        self.save()?;
+        Ok(())
+    }
+
+    /// Like `touch`, but also resets the creation date to now, so the task sorts as
+    /// the newest one by creation date.
+    pub fn restamp(&mut self, title: &str) -> Result<(), String> {
+        let title = self.resolve_title(title)?;
+        let task = self.tasks.get_mut(&title).expect("resolved title exists");
+        task.creation_date = Utc::now().trunc_subsecs(0);
+        task.touch();
+        self.save()?;
+        Ok(())
+    }
+
+    /// Appends a note stamped with the current time to the task.
+    pub fn add_note(&mut self, title: &str, text: &str) -> Result<(), String> {
+        if let Some(task) = self.tasks.get_mut(title) {
+            task.notes.push(Note {
+                text: text.to_string(),
+                created_at: Utc::now(),
+            });
+            task.touch();
+            self.save()?;
+            Ok(())
+        } else {
+            Err(self.not_found(title))
+        }
+    }
+
+    pub fn set_reminder(&mut self, title: &str, at: DateTime<Utc>) -> Result<(), String> {
+        if let Some(task) = self.tasks.get_mut(title) {
+            task.remind_at = Some(at);
+            task.touch();
+            self.save()?;
+            Ok(())
+        } else {
+            Err(self.not_found(title))
+        }
+    }
+
+    /// Sets or clears (`None`) how a task repeats.
+    pub fn set_recurrence(
+        &mut self,
+        title: &str,
+        recurrence: Option<Recurrence>,
+    ) -> Result<(), String> {
+        if let Some(task) = self.tasks.get_mut(title) {
+            task.recurrence = recurrence;
+            task.touch();
+            self.save()?;
+            Ok(())
+        } else {
+            Err(self.not_found(title))
+        }
+    }
+
+    /// Appends a subtask, returning its 1-based index.
+    pub fn add_subtask(&mut self, title: &str, text: &str) -> Result<usize, String> {
+        if let Some(task) = self.tasks.get_mut(title) {
+            task.subtasks.push(Subtask {
+                text: text.to_string(),
+                done: false,
+            });
+            task.touch();
+            let index = task.subtasks.len();
+            self.save()?;
+            Ok(index)
+        } else {
+            Err(self.not_found(title))
+        }
+    }
+
+    /// Links a file path or URL to a task. Attaching the same reference twice is an error.
+    pub fn attach(&mut self, title: &str, reference: &str) -> Result<(), String> {
+        let Some(task) = self.tasks.get_mut(title) else {
+            return Err(self.not_found(title));
+        };
+        if task.attachments.iter().any(|a| a == reference) {
+            return Err(format!(
+                "Task '{}' already has attachment '{}'",
+                title, reference
+            ));
+        }
+        task.attachments.push(reference.to_string());
+        task.touch();
+        self.save()?;
+        Ok(())
+    }
+
+    /// Flips the done flag of the subtask at 1-based `index`, returning its new state.
+    pub fn toggle_subtask(&mut self, title: &str, index: usize) -> Result<bool, String> {
+        let Some(task) = self.tasks.get_mut(title) else {
+            return Err(self.not_found(title));
+        };
+        let count = task.subtasks.len();
+        let subtask = index
+            .checked_sub(1)
+            .and_then(|i| task.subtasks.get_mut(i))
+            .ok_or_else(|| {
+                format!(
+                    "Task '{}' has no subtask {} (it has {})",
+                    title, index, count
+                )
+            })?;
+        subtask.done = !subtask.done;
+        let done = subtask.done;
+        task.touch();
+        self.save()?;
+        Ok(done)
+    }
+
+    /// Adds `minutes` to the time spent on a task.
+    pub fn log_time(&mut self, title: &str, minutes: u32) -> Result<(), String> {
+        if let Some(task) = self.tasks.get_mut(title) {
+            task.spent_minutes = Some(task.spent_minutes.unwrap_or(0).saturating_add(minutes));
+            task.touch();
+            self.save()?;
+            Ok(())
+        } else {
+            Err(self.not_found(title))
+        }
+    }
+
+    /// Sums `(estimated, spent)` minutes across active tasks.
+    pub fn effort_totals(&self) -> (u32, u32) {
+        self.tasks
+            .values()
+            .filter(|task| task.status == TaskStatus::Active)
+            .fold((0, 0), |(estimated, spent), task| {
+                (
+                    estimated + task.estimate_minutes.unwrap_or(0),
+                    spent + task.spent_minutes.unwrap_or(0),
+                )
+            })
+    }
+
+    /// Counts tasks created and completed after `cutoff`.
+    pub fn stats_since(&self, cutoff: DateTime<Utc>) -> TaskStats {
+        let mut stats = TaskStats::default();
+        for task in self.tasks.values() {
+            let done = task.status == TaskStatus::Done;
+            if task.creation_date > cutoff {
+                stats.created += 1;
+                stats.created_and_done += usize::from(done);
+            }
+            if done && task.completed_at.is_some_and(|at| at > cutoff) {
+                stats.completed += 1;
+            }
+        }
+        stats
+    }
+
+    /// Counts tasks per category, ordered by category name.
+    pub fn category_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for task in self.tasks.values() {
+            *counts.entry(task.category.0.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Moves every task in category `from` to category `to` with a single save and
+    /// returns how many were moved. Fails if no task is in `from`.
+    pub fn recategorize(&mut self, from: &str, to: Category) -> Result<usize, String> {
+        let mut moved = 0;
+        for task in self
+            .tasks
+            .values_mut()
+            .filter(|task| task.category.0 == from)
+        {
+            task.category = to.clone();
+            task.touch();
+            moved += 1;
+        }
+        if moved == 0 {
+            return Err(format!("No tasks in category '{}'", from));
+        }
+        self.save()?;
+        Ok(moved)
+    }
+
+    /// Returns tasks modified after `since`, least recently modified first.
+    pub fn changed_since(&self, since: DateTime<Utc>) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self
+            .tasks
+            .values()
+            .filter(|task| task.modified_at() > since)
+            .collect();
+        tasks.sort_by_key(|task| task.modified_at());
+        tasks
+    }
+
+    /// Returns active tasks whose reminder time is at or before `now`, earliest first.
+    pub fn due_reminders(&self, now: DateTime<Utc>) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self
+            .tasks
+            .values()
+            .filter(|task| task.status == TaskStatus::Active)
+            .filter(|task| task.remind_at.is_some_and(|at| at <= now))
+            .collect();
+        tasks.sort_by_key(|task| task.remind_at);
+        tasks
+    }
+
+    /// Replaces the task stored under `title` with `new_task`. If the new title
+    /// differs, the task is re-keyed under it and other tasks' `depends_on`
+    /// entries are updated to follow the rename.
+    pub fn update_task(&mut self, title: &str, mut new_task: Task) -> Result<(), String> {
+        if !self.tasks.contains_key(title) {
+            return Err(self.not_found(title));
+        }
+        new_task.title = new_task.title.trim().to_string();
+        if new_task.title != title {
+            if new_task.title.is_empty() {
+                return Err("Task title cannot be empty".to_string());
+            }
+            if self.tasks.contains_key(&new_task.title) {
+                return Err(format!(
+                    "Task with title '{}' already exists",
+                    new_task.title
+                ));
+            }
+        }
+        if self.creates_cycle(&new_task, title) {
+            return Err(format!(
+                "Task '{}' would create a dependency cycle",
+                new_task.title
+            ));
+        }
+        if new_task.title != title {
+            self.tasks.remove(title);
+            for task in self.tasks.values_mut() {
+                for dep in task.depends_on.iter_mut().filter(|dep| *dep == title) {
+                    *dep = new_task.title.clone();
+                }
+            }
+        }
+        match new_task.status {
+            TaskStatus::Active => new_task.completed_at = None,
+            TaskStatus::Done if new_task.completed_at.is_none() => {
+                new_task.completed_at = Some(Utc::now())
+            }
+            TaskStatus::Done => {}
+        }
+        new_task.touch();
+        self.tasks.insert(new_task.title.clone(), new_task);
+        self.save()?;
+        Ok(())
+    }
+
+    /// Applies the `Some` fields of `patch` to the task stored under `title`, leaving
+    /// the rest as they are. Renames and status changes behave as in `update_task`.
+    pub fn patch_task(&mut self, title: &str, patch: TaskPatch) -> Result<(), String> {
+        let patched = patch.apply(self.find_task(title)?);
+        self.update_task(title, patched)
+    }
+
+    /// Deletes a task. `title` may be a unique prefix of the task's title;
+    /// see `resolve_title`.
+    pub fn delete_task(&mut self, title: &str) -> Result<(), String> {
+        let title = self.resolve_title(title)?;
+        self.tasks.remove(&title);
+        self.save()?;
+        Ok(())
+    }
+
+    /// Deletes each title like `delete_task`, saving once at the end. A failure on one
+    /// title doesn't stop the others; each outcome is the resolved title or an error.
+    pub fn delete_all(&mut self, titles: &[String]) -> Result<Vec<Result<String, String>>, String> {
+        let results: Vec<_> = titles
+            .iter()
+            .map(|title| {
+                let title = self.resolve_title(title)?;
+                self.tasks.remove(&title);
+                Ok(title)
+            })
+            .collect();
+        if results.iter().any(Result::is_ok) {
+            self.save()?;
+        }
+        Ok(results)
+    }
+
+    /// Groups tasks whose titles are equal after normalizing case and whitespace.
+    /// Each group is the oldest task's title (the one `dedupe` keeps) and the titles
+    /// that would be merged into it, oldest first; groups are ordered by kept title.
+    pub fn duplicates(&self) -> Vec<(String, Vec<String>)> {
+        let mut groups: BTreeMap<String, Vec<&Task>> = BTreeMap::new();
+        for task in self.tasks.values() {
+            groups
+                .entry(normalize_title(&task.title))
+                .or_default()
+                .push(task);
+        }
+        let mut duplicates: Vec<(String, Vec<String>)> = groups
+            .into_values()
+            .filter(|tasks| tasks.len() > 1)
+            .map(|mut tasks| {
+                tasks.sort_by(|a, b| {
+                    a.creation_date
+                        .cmp(&b.creation_date)
+                        .then_with(|| a.title.cmp(&b.title))
+                });
+                let removed = tasks[1..].iter().map(|task| task.title.clone()).collect();
+                (tasks[0].title.clone(), removed)
+            })
+            .collect();
+        duplicates.sort();
+        duplicates
+    }
+
+    /// Merges each group found by `duplicates` into its oldest task with a single
+    /// save: the others' notes and any subtasks or attachments it lacks are appended to it,
+    /// dependencies on them are pointed at it, and they are removed. Returns the
+    /// groups that were merged.
+    pub fn dedupe(&mut self) -> Result<Vec<(String, Vec<String>)>, String> {
+        let duplicates = self.duplicates();
+        for (kept, removed) in &duplicates {
+            for title in removed {
+                let Some(task) = self.tasks.remove(title) else {
+                    continue;
+                };
+                let target = self.tasks.get_mut(kept).unwrap();
+                target.notes.extend(task.notes);
+                for subtask in task.subtasks {
+                    if !target.subtasks.iter().any(|s| s.text == subtask.text) {
+                        target.subtasks.push(subtask);
+                    }
+                }
+                for attachment in task.attachments {
+                    if !target.attachments.contains(&attachment) {
+                        target.attachments.push(attachment);
+                    }
+                }
+                target.touch();
+            }
+            for task in self.tasks.values_mut() {
+                if !task.depends_on.iter().any(|dep| removed.contains(dep)) {
+                    continue;
+                }
+                let mut seen = HashSet::new();
+                task.depends_on = std::mem::take(&mut task.depends_on)
+                    .into_iter()
+                    .map(|dep| {
+                        if removed.contains(&dep) {
+                            kept.clone()
+                        } else {
+                            dep
+                        }
+                    })
+                    .filter(|dep| *dep != task.title && seen.insert(dep.clone()))
+                    .collect();
+            }
+        }
+        if !duplicates.is_empty() {
+            self.save()?;
+        }
+        Ok(duplicates)
+    }
+
+    /// Checks the list for inconsistencies such as dangling dependencies or
+    /// near-duplicate titles.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        validate_tasks(&self.tasks)
+    }
+
+    pub fn get_all_tasks(&self) -> Vec<&Task> {
+        self.tasks.values().collect()
+    }
+
+    /// The whole list as a self-contained HTML page, grouped by category.
+    pub fn export_html(&self) -> String {
+        render_html(self.get_all_tasks())
+    }
+
+    /// Iterates over all tasks in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn get_task(&self, title: &str) -> Option<&Task> {
+        self.tasks.get(title)
+    }
+
+    /// Like `get_task`, but returns a "not found" error suggesting a close title on a miss.
+    pub fn find_task(&self, title: &str) -> Result<&Task, String> {
+        self.tasks.get(title).ok_or_else(|| self.not_found(title))
+    }
+
+    /// Returns active tasks that have at least one dependency which is not done.
+    /// Dependencies on titles that don't exist (yet) count as not done.
+    pub fn blocked_tasks(&self) -> Vec<&Task> {
+        self.tasks
+            .values()
+            .filter(|task| task.status == TaskStatus::Active && self.is_blocked(task))
+            .collect()
+    }
+
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.depends_on.iter().any(|dep| {
+            !matches!(self.tasks.get(dep), Some(dep_task) if dep_task.status == TaskStatus::Done)
+        })
+    }
+
+    /// Whether storing `task` in place of the task titled `replacing` (its own title
+    /// when it's new) would make it depend on itself, directly or transitively.
+    fn creates_cycle(&self, task: &Task, replacing: &str) -> bool {
+        let mut stack: Vec<&str> = task.depends_on.iter().map(String::as_str).collect();
+        let mut visited = HashSet::new();
+        while let Some(title) = stack.pop() {
+            if title == task.title || title == replacing {
+                return true;
+            }
+            if !visited.insert(title) {
+                continue;
+            }
+            if let Some(dep_task) = self.tasks.get(title) {
+                stack.extend(dep_task.depends_on.iter().map(String::as_str));
+            }
+        }
+        false
+    }
+
+    /// Returns active tasks due before `cutoff`, most overdue first.
+    pub fn tasks_due_before(&self, cutoff: DateTime<Utc>) -> Vec<&Task> {
+        let mut tasks = self.filter_by(&[
+            Predicate::Status(TaskStatus::Active),
+            Predicate::DueBefore(cutoff),
+        ]);
+        tasks.sort_by_key(|task| task.due);
+        tasks
+    }
+
+    /// Picks the single task to work on next: active and unblocked, ordered by
+    /// highest priority, then earliest due date (undated last), then oldest
+    /// creation date. Title breaks any remaining tie so the pick is stable.
+    pub fn next_task(&self) -> Option<&Task> {
+        self.tasks
+            .values()
+            .filter(|task| task.status == TaskStatus::Active && !self.is_blocked(task))
+            .min_by_key(|task| {
+                (
+                    Reverse(task.priority),
+                    task.due.is_none(),
+                    task.due,
+                    task.creation_date,
+                    &task.title,
+                )
+            })
+    }
+
+    pub fn filter_tasks(&self, predicate: &str) -> Result<Vec<&Task>, String> {
+        Ok(self.filter_by(&parse_predicates(predicate)?))
+    }
+
+    /// Returns the tasks matching every one of `predicates`; all tasks if there are none.
+    pub fn filter_by(&self, predicates: &[Predicate]) -> Vec<&Task> {
+        self.tasks
+            .values()
+            .filter(|task| predicates.iter().all(|p| p.matches(task)))
+            .collect()
+    }
+
+    /// Marks every task matching `predicate` as done and returns how many matched.
+    pub fn mark_done_where(&mut self, predicate: &str) -> Result<usize, String> {
+        let titles = self.matching_titles(predicate)?;
+        for title in &titles {
+            if let Some(task) = self.tasks.get_mut(title) {
+                task.complete();
+            }
+        }
+        if !titles.is_empty() {
+            self.save()?;
+        }
+        Ok(titles.len())
+    }
+
+    /// Deletes every task matching `predicate` and returns how many were removed.
+    pub fn delete_where(&mut self, predicate: &str) -> Result<usize, String> {
+        let titles = self.matching_titles(predicate)?;
+        for title in &titles {
+            self.tasks.remove(title);
+        }
+        if !titles.is_empty() {
+            self.save()?;
+        }
+        Ok(titles.len())
+    }
+
+    fn matching_titles(&self, predicate: &str) -> Result<Vec<String>, String> {
+        Ok(self
+            .filter_tasks(predicate)?
+            .into_iter()
+            .map(|task| task.title.clone())
+            .collect())
+    }
+
+    /// Moves a task into `dest`, saving the destination before removing the task here so
+    /// an interrupted move leaves a duplicate rather than losing the task. Returns the title
+    /// the task ended up with, or `None` if it was skipped because of a conflict.
+    pub fn move_task(
+        &mut self,
+        title: &str,
+        dest: &mut TodoList,
+        strategy: ConflictStrategy,
+    ) -> Result<Option<String>, String> {
+        if self.file_path == dest.file_path {
+            return Err("Source and destination lists are the same file".to_string());
+        }
+        let mut task = self.find_task(title)?.clone();
+        let Some(new_title) = dest.resolve_conflict(title, strategy)? else {
+            return Ok(None);
+        };
+        task.title = new_title.clone();
+        task.touch();
+        dest.tasks.insert(new_title.clone(), task);
+        dest.save()?;
+        self.tasks.remove(title);
+        self.save()?;
+        Ok(Some(new_title))
+    }
+
+    /// Adds every task of `other` to this list, settling title clashes with `strategy`,
+    /// and saves once. With `Fail` nothing is merged if any title clashes.
+    pub fn merge(
+        &mut self,
+        other: TodoList,
+        strategy: ConflictStrategy,
+    ) -> Result<MergeReport, String> {
+        let mut incoming: Vec<Task> = other.tasks.into_values().collect();
+        incoming.sort_by(|a, b| a.title.cmp(&b.title));
+        if strategy == ConflictStrategy::Fail {
+            for task in &incoming {
+                self.resolve_conflict(&task.title, strategy)?;
+            }
+        }
+
+        let total = incoming.len();
+        let mut report = MergeReport::default();
+        for mut task in incoming {
+            let incoming_title = task.title.clone();
+            let taken = self.tasks.contains_key(&incoming_title);
+            let Some(title) = self.resolve_conflict(&incoming_title, strategy)? else {
+                report.skipped.push(incoming_title);
+                continue;
+            };
+            if !taken {
+                report.added.push(title.clone());
+            } else if title == incoming_title {
+                report.overwritten.push(title.clone());
+            } else {
+                report.renamed.push((incoming_title, title.clone()));
+            }
+            task.title = title.clone();
+            task.touch();
+            self.tasks.insert(title, task);
+        }
+        if report.skipped.len() < total {
+            self.save()?;
+        }
+        Ok(report)
+    }
+
+    pub fn archive_path(&self) -> PathBuf {
+        Archive::path_for(&self.file_path)
+    }
+
+    /// Directory external descriptions are written to (`tasks.json` ->
+    /// `tasks.descriptions/`).
+    pub fn descriptions_dir(&self) -> PathBuf {
+        self.file_path.with_extension("descriptions")
+    }
+
+    /// Where a task's `description_file`, stored relative to the list, is on disk.
+    pub fn resolve_description_file(&self, relative: &Path) -> PathBuf {
+        match self.file_path.parent() {
+            Some(dir) => dir.join(relative),
+            None => relative.to_path_buf(),
+        }
+    }
+
+    /// Moves a task's description into its own file under `descriptions_dir`, so long
+    /// texts don't slow down loading the list. The task keeps the start of the first
+    /// line as its description. Returns the file's path.
+    pub fn externalize_description(&mut self, title: &str) -> Result<PathBuf, String> {
+        let Some(task) = self.tasks.get(title) else {
+            return Err(self.not_found(title));
+        };
+        if task.description_file.is_some() {
+            return Err(format!(
+                "Task '{}' already keeps its description in a file",
+                title
+            ));
+        }
+        let dir = self.descriptions_dir();
+        let dir_name = PathBuf::from(dir.file_name().unwrap_or_default());
+        let base = description_file_name(title);
+        let relative = (1..)
+            .map(|n| match n {
+                1 => dir_name.join(format!("{}.txt", base)),
+                n => dir_name.join(format!("{}-{}.txt", base, n)),
+            })
+            .find(|relative| !self.resolve_description_file(relative).exists())
+            .unwrap();
+        let path = self.resolve_description_file(&relative);
+        if !self.dry_run {
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            fs::write(&path, &task.description)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+
+        let task = self.tasks.get_mut(title).unwrap();
+        let first_line = task.description.lines().next().unwrap_or_default();
+        task.description = truncate_str(first_line, DESCRIPTION_SUMMARY_LEN);
+        task.description_file = Some(relative);
+        task.touch();
+        self.save()?;
+        Ok(path)
+    }
+
+    /// A task's full description, read from its description file if it has one.
+    pub fn full_description(&self, task: &Task) -> Result<String, String> {
+        match &task.description_file {
+            Some(relative) => {
+                let path = self.resolve_description_file(relative);
+                fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+            }
+            None => Ok(task.description.clone()),
+        }
+    }
+
+    /// Moves every done task into the archive file and returns how many were moved.
+    /// The list is only saved once the archive has been written.
+    pub fn archive_done(&mut self) -> Result<usize, String> {
+        let mut titles: Vec<String> = self
+            .tasks
+            .values()
+            .filter(|task| task.status == TaskStatus::Done)
+            .map(|task| task.title.clone())
+            .collect();
+        if titles.is_empty() {
+            return Ok(0);
+        }
+        titles.sort();
+        let mut archive = Archive::load(self.archive_path())?;
+        archive.extend(titles.iter().filter_map(|title| self.tasks.remove(title)));
+        if !self.dry_run {
+            archive.save()?;
+        }
+        self.save()?;
+        Ok(titles.len())
+    }
+
+    /// Permanently removes archived tasks completed before `cutoff` (all of them
+    /// without one) and returns how many were removed.
+    pub fn purge_archive(&self, cutoff: Option<DateTime<Utc>>) -> Result<usize, String> {
+        let mut archive = Archive::load(self.archive_path())?;
+        let purged = archive.purge(cutoff);
+        if purged > 0 && !self.dry_run {
+            archive.save()?;
+        }
+        Ok(purged)
+    }
+
+    /// Suggests an existing title close to `input`, for typos in commands that take a title.
+    /// Only near misses (roughly one edit per three characters, at most three) are suggested.
+    pub fn suggest_title(&self, input: &str) -> Option<&str> {
+        let input = input.to_lowercase();
+        let threshold = (input.chars().count() / 3).clamp(1, MAX_SUGGESTION_DISTANCE);
+        self.tasks
+            .keys()
+            .map(|title| (strsim::levenshtein(&input, &title.to_lowercase()), title))
+            .filter(|(distance, _)| *distance <= threshold)
+            .min()
+            .map(|(_, title)| title.as_str())
+    }
+
+    /// Tasks that look like near-duplicates of `task`: same category (ignoring case)
+    /// and at least the similarity threshold of their title and description words in
+    /// common. A task with the same title is left out, as adding it fails anyway.
+    /// Sorted by title.
+    pub fn find_similar(&self, task: &Task) -> Vec<&Task> {
+        let category = task.category.0.to_lowercase();
+        let wanted = words(task);
+        let mut similar: Vec<&Task> = self
+            .tasks
+            .values()
+            .filter(|other| other.title != task.title)
+            .filter(|other| other.category.0.to_lowercase() == category)
+            .filter(|other| word_overlap(&wanted, &words(other)) >= self.similarity_threshold)
+            .collect();
+        similar.sort_by(|a, b| a.title.cmp(&b.title));
+        similar
+    }
+
+    /// Resolves user input to an existing title. An exact match always wins;
+    /// otherwise the input may be a prefix shared by exactly one title.
+    /// Ambiguous prefixes produce an error listing the candidates, and blank input
+    /// is rejected rather than treated as a prefix of every title.
+    pub fn resolve_title(&self, title: &str) -> Result<String, String> {
+        if title.trim().is_empty() {
+            return Err("Task title cannot be empty".to_string());
+        }
+        if self.tasks.contains_key(title) {
+            return Ok(title.to_string());
+        }
+        let mut candidates: Vec<&String> = self
+            .tasks
+            .keys()
+            .filter(|candidate| candidate.starts_with(title))
+            .collect();
+        candidates.sort();
+        match candidates.as_slice() {
+            [] => Err(self.not_found(title)),
+            [only] => Ok(only.to_string()),
+            _ => {
+                let names: Vec<String> = candidates.iter().map(|c| format!("'{}'", c)).collect();
+                Err(format!(
+                    "'{}' matches several tasks: {}",
+                    title,
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+
+    fn not_found(&self, title: &str) -> String {
+        match self.suggest_title(title) {
+            Some(suggestion) => format!(
+                "Task with title '{}' not found. Did you mean '{}'?",
+                title, suggestion
+            ),
+            None => format!("Task with title '{}' not found", title),
+        }
+    }
+
+    /// The title to store an incoming task titled `title` under, or `None` if it
+    /// should be skipped.
+    fn resolve_conflict(
+        &self,
+        title: &str,
+        strategy: ConflictStrategy,
+    ) -> Result<Option<String>, String> {
+        if !self.tasks.contains_key(title) {
+            return Ok(Some(title.to_string()));
+        }
+        match strategy {
+            ConflictStrategy::Fail => Err(format!(
+                "Task with title '{}' already exists in {}",
+                title,
+                self.file_path.display()
+            )),
+            ConflictStrategy::Skip => Ok(None),
+            ConflictStrategy::Overwrite => Ok(Some(title.to_string())),
+            ConflictStrategy::Rename => Ok(Some(self.unused_title(title))),
+        }
+    }
+
+    fn unused_title(&self, title: &str) -> String {
+        (2..)
+            .map(|n| format!("{} ({})", title, n))
+            .find(|candidate| !self.tasks.contains_key(candidate))
+            .unwrap()
+    }
+
+    /// Hands the tasks to the list's `Storage`, after rotating backups of its file.
+    fn save(&mut self) -> Result<(), String> {
+        if self.dry_run {
+            return Ok(());
+        }
+        self.rotate_backups()?;
+        self.storage.save(&self.tasks)
+    }
+
+    fn rotate_backups(&self) -> Result<(), String> {
+        if self.backups == 0 || !self.file_path.exists() {
+            return Ok(());
+        }
+        let numbered = |n: usize| {
+            let mut path = self.file_path.clone().into_os_string();
+            path.push(format!(".{}", n));
+            PathBuf::from(path)
+        };
+        for n in (1..self.backups).rev() {
+            let from = numbered(n);
+            if from.exists() {
+                fs::rename(&from, numbered(n + 1))
+                    .map_err(|e| format!("Failed to rotate backup {}: {}", from.display(), e))?;
+            }
+        }
+        fs::copy(&self.file_path, numbered(1))
+            .map_err(|e| format!("Failed to write backup: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Category, Priority};
+    use crate::test_support::{cleanup_file, get_unique_file_path, setup};
+
+    #[test]
+    fn test_add_task() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        assert!(todo_list.add_task(task).is_ok());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_add_task_rejects_empty_title() {
+        let (mut todo_list, file_path) = setup();
+        for title in ["", "   ", "\t\n"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            assert!(todo_list.add_task(task).is_err());
+        }
+        assert!(todo_list.tasks.is_empty());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_add_task_trims_title() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "  Task ".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        assert!(todo_list.tasks.contains_key("Task"));
+        assert_eq!(todo_list.tasks["Task"].title, "Task");
+
+        let duplicate = Task::new(
+            "Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        assert!(todo_list.add_task(duplicate).is_err());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_dry_run_skips_save() {
+        let (mut todo_list, file_path) = setup();
+        todo_list.set_dry_run(true);
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        assert!(todo_list.tasks.contains_key("Test Task"));
+        assert!(!file_path.exists());
+        assert!(todo_list.mark_as_done("Missing").is_err());
+    }
+
+    #[test]
+    fn test_upsert_task() {
+        let (mut todo_list, file_path) = setup();
+        let mut original = Task::new(
+            "Test Task".to_string(),
+            "Original".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        original.creation_date = Utc::now() - chrono::Duration::days(3);
+        let original_date = original.creation_date;
+        assert!(!todo_list.upsert_task(original, false).unwrap());
+
+        let replacement = Task::new(
+            "Test Task".to_string(),
+            "Replacement".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        assert!(todo_list.add_task(replacement.clone()).is_err());
+        assert!(todo_list.upsert_task(replacement.clone(), true).unwrap());
+        let task = todo_list.get_task("Test Task").unwrap();
+        assert_eq!(task.description, "Replacement");
+        assert_eq!(task.creation_date, original_date);
+
+        assert!(todo_list.upsert_task(replacement, false).unwrap());
+        assert_ne!(
+            todo_list.get_task("Test Task").unwrap().creation_date,
+            original_date
+        );
+        assert_eq!(todo_list.len(), 1);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_archive_and_purge() {
+        let (mut todo_list, file_path) = setup();
+        for title in ["Old", "Recent", "Active"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        todo_list.mark_as_done("Old").unwrap();
+        todo_list.mark_as_done("Recent").unwrap();
+        let mut old = todo_list.get_task("Old").unwrap().clone();
+        old.completed_at = Some(Utc::now() - chrono::Duration::days(40));
+        todo_list.update_task("Old", old).unwrap();
+
+        assert_eq!(todo_list.archive_done(), Ok(2));
+        assert_eq!(todo_list.len(), 1);
+        assert_eq!(todo_list.archive_done(), Ok(0));
+        let archive_path = todo_list.archive_path();
+        assert_eq!(
+            Archive::load(archive_path.clone()).unwrap().tasks().len(),
+            2
+        );
+
+        let cutoff = Utc::now() - chrono::Duration::days(30);
+        assert_eq!(todo_list.purge_archive(Some(cutoff)), Ok(1));
+        let remaining = Archive::load(archive_path.clone()).unwrap();
+        assert_eq!(remaining.tasks().len(), 1);
+        assert_eq!(remaining.tasks()[0].title, "Recent");
+
+        assert_eq!(todo_list.purge_archive(None), Ok(1));
+        assert!(Archive::load(archive_path.clone())
+            .unwrap()
+            .tasks()
+            .is_empty());
+        cleanup_file(&archive_path);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_archive_done_keeps_unreadable_archive() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Finished".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        todo_list.mark_as_done("Finished").unwrap();
+        let archive_path = todo_list.archive_path();
+        fs::write(&archive_path, "not json").unwrap();
+
+        assert!(todo_list.archive_done().is_err());
+        assert!(todo_list.purge_archive(None).is_err());
+        assert_eq!(fs::read_to_string(&archive_path).unwrap(), "not json");
+        assert!(todo_list.get_task("Finished").is_some());
+        cleanup_file(&archive_path);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_update_task_tracks_completion() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        todo_list.mark_as_done("Test Task").unwrap();
+        let done = todo_list.get_task("Test Task").unwrap().clone();
+        assert!(done.completed_at.is_some());
+
+        let reopened = Task {
+            status: TaskStatus::Active,
+            ..done
+        };
+        todo_list.update_task("Test Task", reopened).unwrap();
+        assert!(todo_list
+            .get_task("Test Task")
+            .unwrap()
+            .completed_at
+            .is_none());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_rotating_backups() {
+        let (mut todo_list, file_path) = setup();
+        todo_list.set_backups(2);
+        let backup = |n: usize| PathBuf::from(format!("{}.{}", file_path.display(), n));
+        for title in ["First", "Second", "Third", "Fourth"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        assert_eq!(TodoList::new(backup(1)).len(), 3);
+        assert_eq!(TodoList::new(backup(2)).len(), 2);
+        assert!(!backup(3).exists());
+
+        let copy = todo_list.backup(None).unwrap();
+        assert_eq!(fs::read(&copy).unwrap(), fs::read(&file_path).unwrap());
+        for path in [backup(1), backup(2), copy] {
+            cleanup_file(&path);
+        }
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_failed_backup_is_reported() {
+        let (mut todo_list, file_path) = setup();
+        let task = |title: &str| {
+            Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            )
+        };
+        todo_list.add_task(task("First")).unwrap();
+        todo_list.set_backups(1);
+        let backup = PathBuf::from(format!("{}.1", file_path.display()));
+        fs::create_dir(&backup).unwrap();
+
+        let err = todo_list.add_task(task("Second")).unwrap_err();
+        assert!(err.starts_with("Failed to write backup"));
+        assert_eq!(TodoList::new(file_path.clone()).len(), 1);
+        fs::remove_dir(&backup).unwrap();
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_group_tasks() {
+        let tasks: Vec<Task> = [("B", "work"), ("A", "work"), ("C", "home")]
+            .iter()
+            .map(|(title, category)| {
+                Task::new(
+                    title.to_string(),
+                    "Description".to_string(),
+                    Category(category.to_string()),
+                )
+            })
+            .collect();
+        let groups = group_tasks(tasks.iter().collect(), GroupBy::Category);
+        let layout: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|(key, group)| {
+                (
+                    key.as_str(),
+                    group.iter().map(|task| task.title.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(layout, vec![("home", vec!["C"]), ("work", vec!["A", "B"])]);
+
+        let by_status = group_tasks(tasks.iter().collect(), GroupBy::Status);
+        assert_eq!(by_status.keys().collect::<Vec<_>>(), vec!["on"]);
+        assert!(group_tasks(Vec::new(), GroupBy::Status).is_empty());
+    }
+
+    #[test]
+    fn test_mark_as_done() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        assert!(todo_list.mark_as_done("Test Task").is_ok());
+        assert_eq!(
+            todo_list.tasks.get("Test Task").unwrap().status,
+            TaskStatus::Done
+        );
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_mark_all_done_and_delete_all() {
+        let (mut todo_list, file_path) = setup();
+        for title in ["A", "B", "C"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        let titles = vec!["A".to_string(), "Missing".to_string(), "B".to_string()];
+        let results = todo_list.mark_all_done(&titles).unwrap();
+        assert_eq!(results[0], Ok("A".to_string()));
+        assert!(results[1].is_err());
+        assert_eq!(results[2], Ok("B".to_string()));
+
+        let reloaded = TodoList::new(file_path.clone());
+        assert_eq!(reloaded.tasks["A"].status, TaskStatus::Done);
+        assert_eq!(reloaded.tasks["B"].status, TaskStatus::Done);
+        assert_eq!(reloaded.tasks["C"].status, TaskStatus::Active);
+
+        let titles = vec!["C".to_string(), "A".to_string(), "A".to_string()];
+        let results = todo_list.delete_all(&titles).unwrap();
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert!(results[2].is_err());
+        assert_eq!(TodoList::new(file_path.clone()).len(), 1);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_subtasks() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+
+        assert_eq!(todo_list.add_subtask("Test Task", "First"), Ok(1));
+        assert_eq!(todo_list.add_subtask("Test Task", "Second"), Ok(2));
+        assert!(todo_list.add_subtask("Missing", "Third").is_err());
+
+        assert_eq!(todo_list.toggle_subtask("Test Task", 2), Ok(true));
+        assert!(todo_list.toggle_subtask("Test Task", 0).is_err());
+        assert!(todo_list.toggle_subtask("Test Task", 3).is_err());
+
+        let reloaded = TodoList::new(file_path.clone());
+        let task = reloaded.find_task("Test Task").unwrap();
+        assert_eq!(task.subtask_counts(), (1, 2));
+        assert_eq!(task.subtasks[0].text, "First");
+
+        assert_eq!(todo_list.toggle_subtask("Test Task", 2), Ok(false));
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_add_tasks_renames_conflicts() {
+        let (mut todo_list, file_path) = setup();
+        let task = |title: &str| {
+            Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            )
+        };
+        todo_list.add_task(task("A")).unwrap();
+        assert_eq!(todo_list.add_tasks(vec![task("A"), task("B")]), Ok(2));
+
+        let reloaded = TodoList::new(file_path.clone());
+        assert_eq!(reloaded.len(), 3);
+        assert!(reloaded.get_task("A (2)").is_some());
+        assert!(reloaded.get_task("B").is_some());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_store_format_for_path() {
+        let format = |path: &str| StoreFormat::for_path(Path::new(path));
+        assert_eq!(format("tasks.json"), Ok(Some(StoreFormat::Json)));
+        assert_eq!(format("work/tasks.JSONL"), Ok(Some(StoreFormat::Jsonl)));
+        assert_eq!(format("tasks"), Ok(None));
+        assert_eq!(format("-"), Ok(None));
+        #[cfg(feature = "sqlite")]
+        assert_eq!(format("tasks.db"), Ok(Some(StoreFormat::Sqlite)));
+        #[cfg(not(feature = "sqlite"))]
+        assert!(format("tasks.sqlite3").is_err());
+    }
+
+    #[test]
+    fn test_patch_task() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        let creation_date = task.creation_date;
+        todo_list.add_task(task).unwrap();
+
+        let patch = TaskPatch {
+            status: Some(TaskStatus::Done),
+            ..TaskPatch::default()
+        };
+        todo_list.patch_task("Test Task", patch).unwrap();
+        let task = todo_list.get_task("Test Task").unwrap();
+        assert_eq!(task.status, TaskStatus::Done);
+        assert!(task.completed_at.is_some());
+        assert_eq!(task.description, "Description");
+        assert_eq!(task.creation_date, creation_date);
+        assert_eq!(task.category.0, "TestCategory");
+
+        let patch: TaskPatch =
+            serde_json::from_str(r#"{"title": "Renamed", "priority": "High"}"#).unwrap();
+        todo_list.patch_task("Test Task", patch).unwrap();
+        let task = todo_list.get_task("Renamed").unwrap();
+        assert_eq!(task.priority, Priority::High);
+        assert_eq!(task.status, TaskStatus::Done);
+        assert!(todo_list
+            .patch_task("Missing", TaskPatch::default())
+            .is_err());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_memory_storage() {
+        let storage = MemoryStorage::default();
+        let file_path = get_unique_file_path();
+        let mut todo_list =
+            TodoList::with_storage(file_path.clone(), Box::new(storage.clone())).unwrap();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        todo_list.mark_as_done("Test Task").unwrap();
+
+        assert_eq!(storage.saved()["Test Task"].status, TaskStatus::Done);
+        assert!(!file_path.exists());
+
+        let reopened = TodoList::with_storage(file_path, Box::new(storage)).unwrap();
+        assert_eq!(reopened.len(), 1);
+    }
+
+    #[test]
+    fn test_category_counts() {
+        let (mut todo_list, file_path) = setup();
+        for (title, category) in [("A", "work"), ("B", "home"), ("C", "work")] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category(category.to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        let counts: Vec<_> = todo_list.category_counts().into_iter().collect();
+        assert_eq!(counts, [("home".to_string(), 1), ("work".to_string(), 2)]);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_recategorize() {
+        let (mut todo_list, storage) = memory_list(&[("A", ""), ("B", "")]);
+        let task = Task::new("C".to_string(), String::new(), Category("home".to_string()));
+        todo_list.add_task(task).unwrap();
+
+        let moved = todo_list.recategorize("TestCategory", Category("work".to_string()));
+        assert_eq!(moved, Ok(2));
+        let saved = storage.saved();
+        assert_eq!(saved["A"].category.0, "work");
+        assert_eq!(saved["B"].category.0, "work");
+        assert_eq!(saved["C"].category.0, "home");
+        assert!(saved["A"].last_modified.is_some());
+
+        let err = todo_list.recategorize("TestCategory", Category("work".to_string()));
+        assert_eq!(err, Err("No tasks in category 'TestCategory'".to_string()));
+    }
+
+    #[test]
+    fn test_attach() {
+        let (mut todo_list, storage) = memory_list(&[("Spec", "")]);
+        todo_list.attach("Spec", "docs/spec.md").unwrap();
+        todo_list
+            .attach("Spec", "https://example.com/spec")
+            .unwrap();
+        assert_eq!(
+            storage.saved()["Spec"].attachments,
+            ["docs/spec.md", "https://example.com/spec"]
+        );
+        assert_eq!(
+            todo_list.attach("Spec", "docs/spec.md"),
+            Err("Task 'Spec' already has attachment 'docs/spec.md'".to_string())
+        );
+        assert!(todo_list.attach("Missing", "docs/spec.md").is_err());
+    }
+
+    #[test]
+    fn test_dedupe_keeps_oldest() {
+        let (mut todo_list, storage) = memory_list(&[("Other", "")]);
+        let now = Utc::now();
+        for (title, days_ago, note) in [
+            ("Buy  milk", 1, "second"),
+            ("buy milk", 3, "first"),
+            ("BUY MILK", 2, "third"),
+        ] {
+            let mut task = Task::new(title.to_string(), String::new(), Category("home".into()));
+            task.creation_date = now - chrono::Duration::days(days_ago);
+            task.notes.push(Note {
+                text: note.to_string(),
+                created_at: now,
+            });
+            todo_list.add_task(task).unwrap();
+        }
+        let mut other = todo_list.find_task("Other").unwrap().clone();
+        other.depends_on = vec!["BUY MILK".to_string(), "buy milk".to_string()];
+        todo_list.update_task("Other", other).unwrap();
+
+        let expected = vec![(
+            "buy milk".to_string(),
+            vec!["BUY MILK".to_string(), "Buy  milk".to_string()],
+        )];
+        assert_eq!(todo_list.duplicates(), expected);
+        assert_eq!(todo_list.dedupe(), Ok(expected));
+
+        let saved = storage.saved();
+        assert_eq!(saved.len(), 2);
+        let notes: Vec<&str> = saved["buy milk"]
+            .notes
+            .iter()
+            .map(|n| n.text.as_str())
+            .collect();
+        assert_eq!(notes, ["first", "third", "second"]);
+        assert_eq!(saved["Other"].depends_on, ["buy milk"]);
+        assert!(todo_list.duplicates().is_empty());
+    }
+
+    #[test]
+    fn test_externalize_description() {
+        let (mut todo_list, file_path) = setup();
+        let long = format!("Meeting notes\n{}", "All the details. ".repeat(100));
+        for title in ["Notes: Q3/Q4", "notes q3 q4"] {
+            let task = Task::new(title.to_string(), long.clone(), Category("work".into()));
+            todo_list.add_task(task).unwrap();
+        }
+
+        let path = todo_list.externalize_description("Notes: Q3/Q4").unwrap();
+        let other = todo_list.externalize_description("notes q3 q4").unwrap();
+        assert_eq!(path.file_name().unwrap(), "notes-q3-q4.txt");
+        assert_eq!(other.file_name().unwrap(), "notes-q3-q4-2.txt");
+        assert_eq!(fs::read_to_string(&path).unwrap(), long);
+
+        let reloaded = TodoList::new(file_path.clone());
+        let task = reloaded.get_task("Notes: Q3/Q4").unwrap();
+        assert_eq!(task.description, "Meeting notes");
+        assert_eq!(reloaded.full_description(task).unwrap(), long);
+        assert!(todo_list
+            .externalize_description("Notes: Q3/Q4")
+            .unwrap_err()
+            .contains("already"));
+
+        fs::remove_file(&path).unwrap();
+        let missing = reloaded.full_description(task).unwrap_err();
+        assert!(missing.contains("notes-q3-q4.txt"), "{}", missing);
+        fs::remove_dir_all(todo_list.descriptions_dir()).unwrap();
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_touch_and_restamp() {
+        let (mut todo_list, storage) = memory_list(&[("Review inbox", "")]);
+        let created = Utc::now() - chrono::Duration::days(30);
+        todo_list
+            .tasks
+            .get_mut("Review inbox")
+            .unwrap()
+            .creation_date = created;
+
+        todo_list.touch("Review").unwrap();
+        let touched = storage.saved()["Review inbox"].clone();
+        assert_eq!(touched.creation_date, created);
+        assert!(touched.last_modified.unwrap() > created);
+
+        todo_list.restamp("Review inbox").unwrap();
+        let restamped = &storage.saved()["Review inbox"];
+        assert!(restamped.creation_date > created);
+        assert_eq!(restamped.description, touched.description);
+        assert!(todo_list
+            .touch("Missing")
+            .unwrap_err()
+            .contains("not found"));
+    }
+
+    #[test]
+    fn test_reopen() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        assert!(todo_list.reopen("Test Task").is_err());
+
+        todo_list.mark_as_done("Test Task").unwrap();
+        todo_list.reopen("Test Task").unwrap();
+        let task = todo_list.get_task("Test Task").unwrap();
+        assert_eq!(task.status, TaskStatus::Active);
+        assert!(task.completed_at.is_none());
+        assert!(todo_list.reopen("Missing").is_err());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_delete_task() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        assert!(todo_list.delete_task("Test Task").is_ok());
+        assert!(todo_list.tasks.is_empty());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_filter_tasks() {
+        let (mut todo_list, file_path) = setup();
+        let task1 = Task::new(
+            "Task 1".to_string(),
+            "Description 1".to_string(),
+            Category("Category1".to_string()),
+        );
+        let task2 = Task::new(
+            "Task 2".to_string(),
+            "Description 2".to_string(),
+            Category("Category2".to_string()),
+        );
+        todo_list.add_task(task1).unwrap();
+        todo_list.add_task(task2).unwrap();
+
+        let filtered = todo_list.filter_tasks(r#"category = "Category1""#).unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].title, "Task 1");
+
+        let filtered = todo_list
+            .filter_tasks(r#"description like "Description""#)
+            .unwrap();
+        assert_eq!(filtered.len(), 2);
+
+        assert!(todo_list.filter_tasks("invalid predicate").is_err());
+
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_bulk_done_and_delete_where() {
+        let (mut todo_list, file_path) = setup();
+        for (title, category) in [("Task 1", "work"), ("Task 2", "work"), ("Task 3", "home")] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category(category.to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+
+        assert_eq!(todo_list.mark_done_where(r#"category = "work""#), Ok(2));
+        assert_eq!(
+            todo_list.filter_tasks(r#"status = "done""#).unwrap().len(),
+            2
+        );
+
+        assert_eq!(todo_list.delete_where(r#"status = "done""#), Ok(2));
+        assert_eq!(todo_list.tasks.len(), 1);
+        assert!(todo_list.tasks.contains_key("Task 3"));
+
+        assert_eq!(todo_list.delete_where(r#"category = "nope""#), Ok(0));
+        assert!(todo_list.delete_where("garbage").is_err());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_move_task() {
+        let (mut source, source_path) = setup();
+        let (mut dest, dest_path) = setup();
+        for list in [&mut source, &mut dest] {
+            let task = Task::new(
+                "Shared".to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            list.add_task(task).unwrap();
+        }
+        let task = Task::new(
+            "Personal".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        source.add_task(task).unwrap();
+
+        assert_eq!(
+            source.move_task("Personal", &mut dest, ConflictStrategy::Fail),
+            Ok(Some("Personal".to_string()))
+        );
+        assert!(!source.tasks.contains_key("Personal"));
+        assert!(TodoList::new(dest_path.clone())
+            .tasks
+            .contains_key("Personal"));
+
+        assert!(source
+            .move_task("Shared", &mut dest, ConflictStrategy::Fail)
+            .is_err());
+        assert_eq!(
+            source.move_task("Shared", &mut dest, ConflictStrategy::Skip),
+            Ok(None)
+        );
+        assert!(source.tasks.contains_key("Shared"));
+        assert_eq!(
+            source.move_task("Shared", &mut dest, ConflictStrategy::Rename),
+            Ok(Some("Shared (2)".to_string()))
+        );
+        assert_eq!(dest.tasks.len(), 3);
+        assert!(source.tasks.is_empty());
+        cleanup_file(&source_path);
+        cleanup_file(&dest_path);
+    }
+
+    fn memory_list(descriptions: &[(&str, &str)]) -> (TodoList, MemoryStorage) {
+        let storage = MemoryStorage::new(
+            descriptions
+                .iter()
+                .map(|(title, description)| {
+                    let task = Task::new(
+                        title.to_string(),
+                        description.to_string(),
+                        Category("TestCategory".to_string()),
+                    );
+                    (title.to_string(), task)
+                })
+                .collect(),
+        );
+        let list = TodoList::with_storage(PathBuf::from("memory.json"), Box::new(storage.clone()));
+        (list.unwrap(), storage)
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let incoming = || memory_list(&[("Shared", "theirs"), ("New", "theirs")]).0;
+
+        let (mut list, storage) = memory_list(&[("Shared", "ours")]);
+        let err = list.merge(incoming(), ConflictStrategy::Fail).unwrap_err();
+        assert!(err.contains("'Shared' already exists"));
+        assert_eq!(list.len(), 1);
+        assert!(!storage.saved().contains_key("New"));
+
+        let report = list.merge(incoming(), ConflictStrategy::Skip).unwrap();
+        assert_eq!(report.added, ["New"]);
+        assert_eq!(report.skipped, ["Shared"]);
+        assert_eq!(report.conflicts(), 1);
+        assert_eq!(storage.saved()["Shared"].description, "ours");
+        assert!(storage.saved().contains_key("New"));
+
+        let (mut list, storage) = memory_list(&[("Shared", "ours")]);
+        let report = list.merge(incoming(), ConflictStrategy::Overwrite).unwrap();
+        assert_eq!(report.overwritten, ["Shared"]);
+        assert_eq!(storage.saved()["Shared"].description, "theirs");
+        assert_eq!(list.len(), 2);
+
+        let (mut list, storage) = memory_list(&[("Shared", "ours")]);
+        let report = list.merge(incoming(), ConflictStrategy::Rename).unwrap();
+        assert_eq!(
+            report.renamed,
+            [("Shared".to_string(), "Shared (2)".to_string())]
+        );
+        assert_eq!(storage.saved()["Shared"].description, "ours");
+        assert_eq!(storage.saved()["Shared (2)"].description, "theirs");
+        assert_eq!(list.get_task("Shared (2)").unwrap().title, "Shared (2)");
+
+        let (mut list, _) = memory_list(&[]);
+        let report = list.merge(incoming(), ConflictStrategy::Fail).unwrap();
+        assert_eq!(report.added, ["New", "Shared"]);
+        assert_eq!(report.conflicts(), 0);
+    }
+
+    #[test]
+    fn test_suggest_title() {
+        let (mut todo_list, file_path) = setup();
+        for title in ["Buy groceries", "Call mom"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        assert_eq!(
+            todo_list.suggest_title("buy grocerys"),
+            Some("Buy groceries")
+        );
+        assert_eq!(todo_list.suggest_title("Cal mom"), Some("Call mom"));
+        assert_eq!(todo_list.suggest_title("Walk dog"), None);
+        assert_eq!(todo_list.suggest_title("x"), None);
+
+        let err = todo_list.mark_as_done("Buy grocerys").unwrap_err();
+        assert!(err.contains("Did you mean 'Buy groceries'?"));
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_resolve_title_prefix() {
+        let (mut todo_list, file_path) = setup();
+        for title in ["Buy groceries", "Buy", "Call mom", "Call dad"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        assert_eq!(todo_list.resolve_title("Buy").unwrap(), "Buy");
+        assert_eq!(todo_list.resolve_title("Buy g").unwrap(), "Buy groceries");
+        let err = todo_list.resolve_title("Call").unwrap_err();
+        assert_eq!(err, "'Call' matches several tasks: 'Call dad', 'Call mom'");
+        assert!(todo_list.resolve_title("Walk").is_err());
+
+        todo_list.mark_as_done("Buy g").unwrap();
+        assert_eq!(
+            todo_list.get_task("Buy groceries").unwrap().status,
+            TaskStatus::Done
+        );
+        assert_eq!(
+            todo_list.get_task("Buy").unwrap().status,
+            TaskStatus::Active
+        );
+        todo_list.delete_task("Call m").unwrap();
+        assert!(todo_list.get_task("Call mom").is_none());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_resolve_title_rejects_blank_input() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Only task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        for input in ["", "   ", "\t"] {
+            assert_eq!(
+                todo_list.resolve_title(input),
+                Err("Task title cannot be empty".to_string())
+            );
+        }
+        assert!(todo_list.delete_task("").is_err());
+        assert!(todo_list.get_task("Only task").is_some());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_filter_tasks_category_with_spaces() {
+        let (mut todo_list, file_path) = setup();
+        let task1 = Task::new(
+            "Task 1".to_string(),
+            "Description 1".to_string(),
+            Category("My Project".to_string()),
+        );
+        let task2 = Task::new(
+            "Task 2".to_string(),
+            "Description 2".to_string(),
+            Category("My".to_string()),
+        );
+        todo_list.add_task(task1).unwrap();
+        todo_list.add_task(task2).unwrap();
+
+        let filtered = todo_list
+            .filter_tasks(r#"category = "My Project" and status = "on""#)
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].title, "Task 1");
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_filter_tasks_by_status_alias() {
+        let (mut todo_list, file_path) = setup();
+        let task1 = Task::new(
+            "Task 1".to_string(),
+            "Description 1".to_string(),
+            Category("Category1".to_string()),
+        );
+        let task2 = Task::new(
+            "Task 2".to_string(),
+            "Description 2".to_string(),
+            Category("Category1".to_string()),
+        );
+        todo_list.add_task(task1).unwrap();
+        todo_list.add_task(task2).unwrap();
+        todo_list.mark_as_done("Task 2").unwrap();
+
+        let filtered = todo_list.filter_tasks(r#"status = "d""#).unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].title, "Task 2");
+
+        let filtered = todo_list.filter_tasks(r#"status = "a""#).unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].title, "Task 1");
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_read_api() {
+        let (mut todo_list, file_path) = setup();
+        assert!(todo_list.is_empty());
+        for title in ["Task 1", "Task 2"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        assert_eq!(todo_list.len(), 2);
+        assert!(!todo_list.is_empty());
+        let mut titles: Vec<&str> = todo_list.iter().map(|task| task.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Task 1", "Task 2"]);
+        assert_eq!(todo_list.get_task("Task 1").unwrap().title, "Task 1");
+        assert!(todo_list.find_task("Task 3").is_err());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_update_task() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+
+        let updated_task = Task {
+            title: "Test Task".to_string(),
+            description: "Updated Description".to_string(),
+            creation_date: Utc::now(),
+            category: Category("UpdatedCategory".to_string()),
+            status: TaskStatus::Done,
+            depends_on: Vec::new(),
+            due: None,
+            notes: Vec::new(),
+            remind_at: None,
+            label: None,
+            priority: Priority::Medium,
+            estimate_minutes: None,
+            spent_minutes: None,
+            last_modified: None,
+            completed_at: None,
+            assignee: None,
+            subtasks: Vec::new(),
+            created_by: String::new(),
+            recurrence: None,
+            description_file: None,
+            attachments: Vec::new(),
+        };
+
+        assert!(todo_list.update_task("Test Task", updated_task).is_ok());
+
+        let updated = todo_list.tasks.get("Test Task").unwrap();
+        assert_eq!(updated.description, "Updated Description");
+        assert_eq!(updated.category.0, "UpdatedCategory");
+        assert_eq!(updated.status, TaskStatus::Done);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_update_task_renames_key() {
+        let (mut todo_list, file_path) = setup();
+        for title in ["Old Title", "Other"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        let mut dependent = Task::new(
+            "Dependent".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        dependent.depends_on = vec!["Old Title".to_string()];
+        todo_list.add_task(dependent).unwrap();
+
+        let mut renamed = todo_list.get_task("Old Title").unwrap().clone();
+        renamed.title = "Other".to_string();
+        assert!(todo_list.update_task("Old Title", renamed.clone()).is_err());
+        assert!(todo_list.get_task("Old Title").is_some());
+
+        renamed.title = "New Title".to_string();
+        todo_list.update_task("Old Title", renamed).unwrap();
+        assert!(todo_list.get_task("Old Title").is_none());
+        assert_eq!(todo_list.get_task("New Title").unwrap().title, "New Title");
+        assert_eq!(todo_list.len(), 3);
+        assert_eq!(
+            todo_list.get_task("Dependent").unwrap().depends_on,
+            vec!["New Title"]
+        );
+
+        let reloaded = TodoList::new(file_path.clone());
+        assert!(reloaded.tasks.contains_key("New Title"));
+        assert!(!reloaded.tasks.contains_key("Old Title"));
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_add_note() {
+        let (mut todo_list, file_path) = setup();
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(task).unwrap();
+        todo_list.add_note("Test Task", "First").unwrap();
+        todo_list.add_note("Test Task", "Second").unwrap();
+        assert!(todo_list.add_note("Missing", "Text").is_err());
+
+        let reloaded = TodoList::new(file_path.clone());
+        let notes: Vec<&str> = reloaded.tasks["Test Task"]
+            .notes
+            .iter()
+            .map(|note| note.text.as_str())
+            .collect();
+        assert_eq!(notes, vec!["First", "Second"]);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_stats_since() {
+        let (mut todo_list, _) = memory_list(&[("Old done", ""), ("Old open", "")]);
+        let now = Utc::now();
+        let cutoff = now - chrono::Duration::days(7);
+        for title in ["Old done", "Old open"] {
+            todo_list.tasks.get_mut(title).unwrap().creation_date =
+                now - chrono::Duration::days(30);
+        }
+        assert!(todo_list.stats_since(cutoff).is_empty());
+        todo_list.mark_as_done("Old done").unwrap();
+        for title in ["New done", "New open", "New open 2"] {
+            let task = Task::new(title.to_string(), String::new(), Category("work".into()));
+            todo_list.add_task(task).unwrap();
+        }
+        todo_list.mark_as_done("New done").unwrap();
+
+        let stats = todo_list.stats_since(cutoff);
+        assert_eq!(
+            stats,
+            TaskStats {
+                created: 3,
+                completed: 2,
+                created_and_done: 1,
+            }
+        );
+        assert_eq!(stats.completion_rate(), Some(1.0 / 3.0));
+        let later = todo_list.stats_since(now + chrono::Duration::days(1));
+        assert!(later.is_empty());
+        assert_eq!(later.completion_rate(), None);
+    }
+
+    #[test]
+    fn test_log_time_and_effort_totals() {
+        let (mut todo_list, file_path) = setup();
+        for (title, estimate) in [("First", Some(60)), ("Second", Some(30)), ("Third", None)] {
+            let mut task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            task.estimate_minutes = estimate;
+            todo_list.add_task(task).unwrap();
+        }
+        todo_list.log_time("First", 20).unwrap();
+        todo_list.log_time("First", 15).unwrap();
+        todo_list.log_time("Third", 10).unwrap();
+        assert!(todo_list.log_time("Missing", 10).is_err());
+        assert_eq!(todo_list.get_task("First").unwrap().spent_minutes, Some(35));
+        assert_eq!(todo_list.effort_totals(), (90, 45));
+
+        todo_list.mark_as_done("First").unwrap();
+        assert_eq!(todo_list.effort_totals(), (30, 10));
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_changed_since() {
+        let (mut todo_list, file_path) = setup();
+        let mut old = Task::new(
+            "Old".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        old.creation_date = Utc::now() - chrono::Duration::days(2);
+        todo_list.add_task(old).unwrap();
+        let mut legacy = todo_list.get_task("Old").unwrap().clone();
+        legacy.title = "Legacy".to_string();
+        legacy.last_modified = None;
+        todo_list.tasks.insert(legacy.title.clone(), legacy);
+
+        let since = Utc::now() - chrono::Duration::days(1);
+        let titles = |list: &TodoList| -> Vec<String> {
+            list.changed_since(since)
+                .iter()
+                .map(|task| task.title.clone())
+                .collect()
+        };
+        assert_eq!(titles(&todo_list), vec!["Old"]);
+
+        let checkpoint = Utc::now();
+        assert!(todo_list.changed_since(checkpoint).is_empty());
+        todo_list.add_note("Old", "Progress").unwrap();
+        let changed = todo_list.changed_since(checkpoint);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].title, "Old");
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_due_reminders() {
+        let (mut todo_list, file_path) = setup();
+        let now = Utc::now();
+        for title in ["Past", "Future", "Done"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        todo_list
+            .set_reminder("Past", now - chrono::Duration::minutes(5))
+            .unwrap();
+        todo_list
+            .set_reminder("Future", now + chrono::Duration::hours(1))
+            .unwrap();
+        todo_list
+            .set_reminder("Done", now - chrono::Duration::hours(1))
+            .unwrap();
+        todo_list.mark_as_done("Done").unwrap();
+        assert!(todo_list.set_reminder("Missing", now).is_err());
+
+        let due = todo_list.due_reminders(now);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].title, "Past");
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_blocked_tasks() {
+        let (mut todo_list, file_path) = setup();
+        let first = Task::new(
+            "First".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        let mut second = Task::new(
+            "Second".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        second.depends_on = vec!["First".to_string()];
+        todo_list.add_task(first).unwrap();
+        todo_list.add_task(second).unwrap();
+
+        let blocked = todo_list.blocked_tasks();
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[0].title, "Second");
+
+        todo_list.mark_as_done("First").unwrap();
+        assert!(todo_list.blocked_tasks().is_empty());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_dependency_cycle_rejected() {
+        let (mut todo_list, file_path) = setup();
+        let mut first = Task::new(
+            "First".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        first.depends_on = vec!["Second".to_string()];
+        let mut second = Task::new(
+            "Second".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        second.depends_on = vec!["First".to_string()];
+        todo_list.add_task(first).unwrap();
+        assert!(todo_list.add_task(second).is_err());
+
+        let mut itself = Task::new(
+            "Itself".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        itself.depends_on = vec!["Itself".to_string()];
+        assert!(todo_list.add_task(itself).is_err());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_update_task_rejects_dependency_cycle() {
+        let (mut todo_list, file_path) = setup();
+        let mut first = Task::new(
+            "First".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        first.depends_on = vec!["Second".to_string()];
+        let second = Task::new(
+            "Second".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(first).unwrap();
+        todo_list.add_task(second.clone()).unwrap();
+
+        let mut looped = second.clone();
+        looped.depends_on = vec!["First".to_string()];
+        let err = todo_list.update_task("Second", looped).unwrap_err();
+        assert_eq!(err, "Task 'Second' would create a dependency cycle");
+        assert!(todo_list.get_task("Second").unwrap().depends_on.is_empty());
+
+        let mut renamed = second;
+        renamed.title = "Renamed".to_string();
+        renamed.depends_on = vec!["First".to_string()];
+        assert!(todo_list.update_task("Second", renamed).is_err());
+        assert!(todo_list.get_task("Second").is_some());
+        assert_eq!(
+            todo_list.get_task("First").unwrap().depends_on,
+            vec!["Second".to_string()]
+        );
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_tasks_due_before() {
+        let (mut todo_list, file_path) = setup();
+        let now = Utc::now();
+        let mut later = Task::new(
+            "Later".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        later.due = Some(now - chrono::Duration::hours(1));
+        let mut earlier = Task::new(
+            "Earlier".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        earlier.due = Some(now - chrono::Duration::days(2));
+        let mut future = Task::new(
+            "Future".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        future.due = Some(now + chrono::Duration::days(1));
+        let undated = Task::new(
+            "Undated".to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        todo_list.add_task(later).unwrap();
+        todo_list.add_task(earlier).unwrap();
+        todo_list.add_task(future).unwrap();
+        todo_list.add_task(undated).unwrap();
+
+        let overdue = todo_list.tasks_due_before(now);
+        let titles: Vec<&str> = overdue.iter().map(|task| task.title.as_str()).collect();
+        assert_eq!(titles, vec!["Earlier", "Later"]);
+
+        todo_list.mark_as_done("Earlier").unwrap();
+        assert_eq!(todo_list.tasks_due_before(now).len(), 1);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_next_task() {
+        let (mut todo_list, file_path) = setup();
+        assert!(todo_list.next_task().is_none());
+
+        let now = Utc::now();
+        let new_task = |title: &str, priority: Priority, due: Option<DateTime<Utc>>| {
+            let mut task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            task.priority = priority;
+            task.due = due;
+            task
+        };
+        todo_list
+            .add_task(new_task("Low", Priority::Low, Some(now)))
+            .unwrap();
+        todo_list
+            .add_task(new_task("Undated", Priority::High, None))
+            .unwrap();
+        todo_list
+            .add_task(new_task(
+                "Due later",
+                Priority::High,
+                Some(now + chrono::Duration::days(2)),
+            ))
+            .unwrap();
+        todo_list
+            .add_task(new_task(
+                "Due soon",
+                Priority::High,
+                Some(now + chrono::Duration::days(1)),
+            ))
+            .unwrap();
+        assert_eq!(todo_list.next_task().unwrap().title, "Due soon");
+
+        let mut blocked = new_task("Blocked", Priority::High, Some(now));
+        blocked.depends_on = vec!["Low".to_string()];
+        todo_list.add_task(blocked).unwrap();
+        assert_eq!(todo_list.next_task().unwrap().title, "Due soon");
+
+        todo_list.mark_as_done("Due soon").unwrap();
+        todo_list.mark_as_done("Due later").unwrap();
+        assert_eq!(todo_list.next_task().unwrap().title, "Undated");
+
+        todo_list.mark_as_done("Undated").unwrap();
+        assert_eq!(todo_list.next_task().unwrap().title, "Low");
+        todo_list.mark_as_done("Low").unwrap();
+        assert_eq!(todo_list.next_task().unwrap().title, "Blocked");
+        todo_list.mark_as_done("Blocked").unwrap();
+        assert!(todo_list.next_task().is_none());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_save_is_pretty_and_sorted() {
+        let (mut todo_list, file_path) = setup();
+        for title in ["Charlie", "Alpha", "Bravo"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert!(content.lines().count() > 1);
+        let alpha = content.find("\"Alpha\":").unwrap();
+        let bravo = content.find("\"Bravo\":").unwrap();
+        let charlie = content.find("\"Charlie\":").unwrap();
+        assert!(alpha < bravo && bravo < charlie);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_load_compact_format() {
+        let file_path = get_unique_file_path();
+        fs::write(
+            &file_path,
+            r#"{"B":{"title":"B","description":"d","creation_date":"2023-05-20T10:00:00+02:00","category":"c","status":"Active"},"A":{"title":"A","description":"d","creation_date":"2023-05-20T10:00:00+02:00","category":"c","status":"Done"}}"#,
+        )
+        .unwrap();
+        let todo_list = TodoList::new(file_path.clone());
+        assert_eq!(todo_list.tasks.len(), 2);
+        assert_eq!(todo_list.tasks["A"].status, TaskStatus::Done);
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_reader_writer_round_trip() {
+        let mut todo_list = TodoList::from_reader(std::io::empty()).unwrap();
+        todo_list.set_dry_run(true);
+        for title in ["Bravo", "Alpha"] {
+            let task = Task::new(
+                title.to_string(),
+                "Description".to_string(),
+                Category("TestCategory".to_string()),
+            );
+            todo_list.add_task(task).unwrap();
+        }
+        todo_list.mark_as_done("Alpha").unwrap();
+
+        let mut buffer = Vec::new();
+        todo_list.to_writer(&mut buffer).unwrap();
+        let reloaded = TodoList::from_reader(buffer.as_slice()).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.tasks["Alpha"], todo_list.tasks["Alpha"]);
+        assert_eq!(reloaded.tasks["Bravo"], todo_list.tasks["Bravo"]);
+
+        assert!(TodoList::from_reader("not json".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_load_and_save_versioned_format() {
+        let file_path = get_unique_file_path();
+        fs::write(
+            &file_path,
+            r#"{"version":1,"tasks":{"A":{"title":"A","description":"d","creation_date":"2023-05-20T08:00:00Z","category":"c","status":"Active"}}}"#,
+        )
+        .unwrap();
+        let mut todo_list = TodoList::new(file_path.clone());
+        assert_eq!(todo_list.len(), 1);
+        assert_eq!(todo_list.tasks["A"].category.0, "c");
+
+        todo_list.mark_as_done("A").unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&file_path).unwrap()).unwrap();
+        assert_eq!(saved["version"], SCHEMA_VERSION);
+        assert_eq!(saved["tasks"]["A"]["status"], "Done");
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_newer_schema_version_is_an_error() {
+        let file_path = get_unique_file_path();
+        for content in [
+            r#"{"version":999,"tasks":{"A":{"title":"A","description":"d","creation_date":"2023-05-20T08:00:00Z","category":"c","status":"Active"}}}"#,
+            r#"{"version":999,"tasks":[{"id":1,"name":"A"}]}"#,
+        ] {
+            fs::write(&file_path, content).unwrap();
+            let err = TodoList::with_format(file_path.clone(), StoreFormat::Json).unwrap_err();
+            assert_eq!(
+                err,
+                format!(
+                    "Task file has schema version 999, but this version of todo only supports up to {}",
+                    SCHEMA_VERSION
+                )
+            );
+            assert_eq!(fs::read_to_string(&file_path).unwrap(), content);
+        }
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_legacy_map_with_envelope_like_titles() {
+        let file_path = get_unique_file_path();
+        fs::write(
+            &file_path,
+            r#"{"version":{"title":"version","description":"d","creation_date":"2023-05-20T08:00:00Z","category":"c","status":"Active"},"tasks":{"title":"tasks","description":"d","creation_date":"2023-05-20T08:00:00Z","category":"c","status":"Active"}}"#,
+        )
+        .unwrap();
+        let todo_list = TodoList::new(file_path.clone());
+        assert_eq!(todo_list.len(), 2);
+        assert!(todo_list.get_task("version").is_some());
+        cleanup_file(&file_path);
+    }
+
+    #[test]
+    fn test_find_similar() {
+        let (mut list, _) = memory_list(&[
+            ("Buy milk", "From the corner shop"),
+            ("Buy bread", "Sourdough from the bakery"),
+        ]);
+        let mut other_category = Task::new(
+            "Milk buy".to_string(),
+            "From the corner shop".to_string(),
+            Category("Errands".to_string()),
+        );
+        list.add_task(other_category.clone()).unwrap();
+
+        let mut candidate = Task::new(
+            "Buy some milk".to_string(),
+            "from the corner shop".to_string(),
+            Category("testcategory".to_string()),
+        );
+        let titles = |similar: Vec<&Task>| -> Vec<String> {
+            similar.into_iter().map(|task| task.title.clone()).collect()
+        };
+        assert_eq!(titles(list.find_similar(&candidate)), ["Buy milk"]);
+
+        other_category.title = "Buy milk".to_string();
+        assert_eq!(titles(list.find_similar(&other_category)), ["Milk buy"]);
+
+        list.set_similarity_threshold(0.9);
+        assert!(list.find_similar(&candidate).is_empty());
+        list.set_similarity_threshold(0.1);
+        candidate.description = String::new();
+        assert_eq!(
+            titles(list.find_similar(&candidate)),
+            ["Buy bread", "Buy milk"]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use crate::date::to_local;
+use crate::task::Task;
+
+const HEADER: [&str; 7] = [
+    "title",
+    "description",
+    "status",
+    "category",
+    "priority",
+    "date",
+    "due",
+];
+
+/// Renders tasks as tab-separated values with a header row, one task per line.
+/// Tabs, newlines and backslashes inside fields are escaped as `\t`, `\n`,
+/// `\r` and `\\` so every record stays on a single line.
+pub fn render_tsv(tasks: &[&Task]) -> String {
+    let mut out = HEADER.join("\t");
+    out.push('\n');
+    for task in tasks {
+        let fields = [
+            task.title.clone(),
+            task.description.clone(),
+            task.status.to_string(),
+            task.category.to_string(),
+            task.priority.to_string(),
+            to_local(task.creation_date).to_string(),
+            task.due
+                .map(|due| to_local(due).to_string())
+                .unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| escape(field)).collect();
+        out.push_str(&fields.join("\t"));
+        out.push('\n');
+    }
+    out
+}
+
+fn escape(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Category;
+
+    #[test]
+    fn test_render_tsv() {
+        let task = Task::new(
+            "Test Task".to_string(),
+            "Line one\nline\ttwo \\ end".to_string(),
+            Category("TestCategory".to_string()),
+        );
+        let tsv = render_tsv(&[&task]);
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "title\tdescription\tstatus\tcategory\tpriority\tdate\tdue"
+        );
+        let columns: Vec<&str> = lines[1].split('\t').collect();
+        assert_eq!(columns.len(), 7);
+        assert_eq!(columns[1], "Line one\\nline\\ttwo \\\\ end");
+        assert_eq!(columns[2], "on");
+        assert_eq!(columns[6], "");
+    }
+}
@@ -0,0 +1,112 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::task::TaskStatus;
+use crate::todo_list::TodoList;
+
+const HELP: &str = "↑/↓ move  space toggle done  d delete  q quit";
+
+/// Runs the full-screen task browser until the user quits. Every change goes through
+/// the regular `TodoList` methods, so it is saved immediately.
+pub fn run(todo_list: &mut TodoList) -> Result<(), String> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, todo_list);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, todo_list: &mut TodoList) -> Result<(), String> {
+    let mut state = ListState::default().with_selected(Some(0));
+    let mut message = String::new();
+    loop {
+        let titles = sorted_titles(todo_list);
+        if titles.is_empty() {
+            state.select(None);
+        } else {
+            let selected = state.selected().unwrap_or(0).min(titles.len() - 1);
+            state.select(Some(selected));
+        }
+
+        terminal
+            .draw(|frame| draw(frame, todo_list, &titles, &mut state, &message))
+            .map_err(|e| e.to_string())?;
+
+        let Event::Key(key) = event::read().map_err(|e| e.to_string())? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let selected = state.selected().map(|i| titles[i].as_str());
+        let result = match (key.code, selected) {
+            (KeyCode::Char('q') | KeyCode::Esc, _) => return Ok(()),
+            (KeyCode::Down | KeyCode::Char('j'), _) => {
+                state.select_next();
+                Ok(())
+            }
+            (KeyCode::Up | KeyCode::Char('k'), _) => {
+                state.select_previous();
+                Ok(())
+            }
+            (KeyCode::Char(' '), Some(title)) => toggle_done(todo_list, title),
+            (KeyCode::Char('d'), Some(title)) => todo_list.delete_task(title),
+            _ => Ok(()),
+        };
+        message = result.err().unwrap_or_default();
+    }
+}
+
+fn sorted_titles(todo_list: &TodoList) -> Vec<String> {
+    let mut titles: Vec<String> = todo_list.iter().map(|task| task.title.clone()).collect();
+    titles.sort();
+    titles
+}
+
+fn toggle_done(todo_list: &mut TodoList, title: &str) -> Result<(), String> {
+    match todo_list.find_task(title)?.status {
+        TaskStatus::Active => todo_list.mark_as_done(title),
+        TaskStatus::Done => todo_list.reopen(title),
+    }
+}
+
+fn draw(
+    frame: &mut Frame,
+    todo_list: &TodoList,
+    titles: &[String],
+    state: &mut ListState,
+    message: &str,
+) {
+    let [list_area, footer_area] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+    let items: Vec<ListItem> = titles
+        .iter()
+        .filter_map(|title| todo_list.get_task(title))
+        .map(|task| {
+            let checkbox = if task.status == TaskStatus::Done {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            let blocked = if task.status == TaskStatus::Active && todo_list.is_blocked(task) {
+                " [blocked]"
+            } else {
+                ""
+            };
+            ListItem::new(format!(
+                "{} {} - {}{}",
+                checkbox, task.title, task.category, blocked
+            ))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title(format!(" Tasks ({}) ", titles.len())))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, list_area, state);
+
+    let footer = if message.is_empty() { HELP } else { message };
+    frame.render_widget(Paragraph::new(footer), footer_area);
+}
@@ -0,0 +1,191 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::color::parse_label;
+use crate::task::{Category, Task};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found by `TodoList::validate`, attributed to the task it was found on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub title: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn new(severity: Severity, title: &str, message: String) -> Self {
+        ValidationIssue {
+            severity,
+            title: title.to_string(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: '{}': {}", self.severity, self.title, self.message)
+    }
+}
+
+/// Lowercases a title and collapses its whitespace, so titles that differ only in
+/// case or spacing compare equal.
+pub(crate) fn normalize_title(title: &str) -> String {
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Checks a loaded task map for problems the loader doesn't reject, ordered by title.
+pub(crate) fn validate_tasks(tasks: &HashMap<String, Task>) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut similar: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+
+    for (key, task) in tasks {
+        if key != &task.title {
+            issues.push(ValidationIssue::new(
+                Severity::Error,
+                key,
+                format!(
+                    "stored under a key that differs from its title '{}'",
+                    task.title
+                ),
+            ));
+        }
+        if task.title.trim().is_empty() {
+            issues.push(ValidationIssue::new(
+                Severity::Error,
+                key,
+                "title is blank".to_string(),
+            ));
+        }
+        if let Err(e) = task.category.0.parse::<Category>() {
+            issues.push(ValidationIssue::new(Severity::Error, key, e));
+        }
+        for dep in &task.depends_on {
+            if !tasks.contains_key(dep) {
+                issues.push(ValidationIssue::new(
+                    Severity::Error,
+                    key,
+                    format!("depends on missing task '{}'", dep),
+                ));
+            }
+        }
+        if let Some(due) = task.due.filter(|due| *due < task.creation_date) {
+            issues.push(ValidationIssue::new(
+                Severity::Warning,
+                key,
+                format!(
+                    "due date {} is before its creation date {}",
+                    due, task.creation_date
+                ),
+            ));
+        }
+        if let Some(Err(e)) = task.label.as_deref().map(parse_label) {
+            issues.push(ValidationIssue::new(Severity::Warning, key, e));
+        }
+        similar
+            .entry(normalize_title(&task.title))
+            .or_default()
+            .push(key);
+    }
+
+    for mut titles in similar.into_values().filter(|titles| titles.len() > 1) {
+        titles.sort();
+        let others: Vec<String> = titles[1..].iter().map(|t| format!("'{}'", t)).collect();
+        issues.push(ValidationIssue::new(
+            Severity::Warning,
+            titles[0],
+            format!(
+                "differs only in case or whitespace from {}",
+                others.join(", ")
+            ),
+        ));
+    }
+
+    issues.sort_by(|a, b| a.title.cmp(&b.title).then(b.severity.cmp(&a.severity)));
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn task(title: &str) -> Task {
+        Task::new(
+            title.to_string(),
+            "Description".to_string(),
+            Category("TestCategory".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_validate_clean_list() {
+        let mut tasks = HashMap::new();
+        let mut b = task("B");
+        b.depends_on = vec!["A".to_string()];
+        b.label = Some("red".to_string());
+        tasks.insert("A".to_string(), task("A"));
+        tasks.insert("B".to_string(), b);
+        assert!(validate_tasks(&tasks).is_empty());
+    }
+
+    #[test]
+    fn test_validate_reports_issues() {
+        let mut tasks = HashMap::new();
+        let mut deps = task("Deps");
+        deps.depends_on = vec!["Gone".to_string()];
+        let mut early = task("Early");
+        early.due = Some(early.creation_date - Duration::days(1));
+        early.label = Some("not a color".to_string());
+        let mut blank = task("Blank category");
+        blank.category = Category(" ".to_string());
+        for task in [
+            deps,
+            early,
+            blank,
+            task("Meeting  notes"),
+            task("meeting notes"),
+        ] {
+            tasks.insert(task.title.clone(), task);
+        }
+        tasks.insert("Renamed".to_string(), task("Original"));
+
+        let issues = validate_tasks(&tasks);
+        let summary: Vec<(Severity, &str)> = issues
+            .iter()
+            .map(|issue| (issue.severity, issue.title.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Severity::Error, "Blank category"),
+                (Severity::Error, "Deps"),
+                (Severity::Warning, "Early"),
+                (Severity::Warning, "Early"),
+                (Severity::Warning, "Meeting  notes"),
+                (Severity::Error, "Renamed"),
+            ]
+        );
+        assert!(issues[1].message.contains("'Gone'"));
+        assert!(issues[4].message.contains("'meeting notes'"));
+    }
+}
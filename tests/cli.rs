@@ -0,0 +1,875 @@
+use std::fs;
+use std::io::Write;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// A per-test directory, so each test gets its own `tasks.json`. It is removed when
+/// dropped, including when the test panics.
+struct ScratchDir(PathBuf);
+
+impl Deref for ScratchDir {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Creates an empty scratch directory for one test.
+fn scratch_dir(name: &str) -> ScratchDir {
+    let dir = std::env::temp_dir().join(format!("todo_list_cli_{}_{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    ScratchDir(dir)
+}
+
+/// Runs the binary in `dir`, isolated from the user's config.
+fn todo(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_todo_list"))
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_quiet_suppresses_confirmations() {
+    let dir = scratch_dir("quiet");
+    let output = todo(
+        &dir,
+        &["-q", "add", "Task", "x", "2024-01-02 10:00", "work"],
+    );
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty(), "{}", stdout(&output));
+    let output = todo(&dir, &["--quiet", "done", "Task"]);
+    assert!(output.stdout.is_empty(), "{}", stdout(&output));
+
+    let output = todo(&dir, &["--quiet", "done", "Missing"]);
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("'Missing' not found"), "{}", stderr);
+
+    let output = todo(&dir, &["--quiet", "list"]);
+    assert!(stdout(&output).contains("Task"));
+}
+
+#[test]
+fn test_exit_codes() {
+    let dir = scratch_dir("exit_codes");
+    let output = todo(&dir, &["add", "Task", "x", "2024-01-02 10:00", "work"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let not_found = todo(&dir, &["done", "Missing"]);
+    assert_eq!(not_found.status.code(), Some(1));
+    let duplicate = todo(&dir, &["add", "Task", "x", "2024-01-02 10:00", "work"]);
+    assert_eq!(duplicate.status.code(), Some(1));
+    let bad_predicate = todo(&dir, &["select", r#"bogus = "x""#]);
+    assert_eq!(bad_predicate.status.code(), Some(1));
+
+    let bad_argument = todo(&dir, &["add", "Other", "x", "--bogus"]);
+    assert_eq!(bad_argument.status.code(), Some(2));
+    let bad_date = todo(&dir, &["add", "Other", "x", "not a date", "work"]);
+    assert_eq!(bad_date.status.code(), Some(2));
+
+    let no_matches = todo(&dir, &["select", r#"category = "none""#]);
+    assert_eq!(no_matches.status.code(), Some(0));
+}
+
+#[test]
+fn test_count() {
+    let dir = scratch_dir("count");
+    assert_eq!(stdout(&todo(&dir, &["count"])), "0\n");
+    for (title, category) in [("A", "work"), ("B", "work"), ("C", "home")] {
+        todo(&dir, &["add", title, "x", "2024-01-02 10:00", category]);
+    }
+
+    assert_eq!(stdout(&todo(&dir, &["count"])), "3\n");
+    assert_eq!(
+        stdout(&todo(&dir, &["count", r#"category = "work""#])),
+        "2\n"
+    );
+    assert_eq!(
+        stdout(&todo(&dir, &["count", r#"category = "none""#])),
+        "0\n"
+    );
+    assert!(!todo(&dir, &["count", "bogus"]).status.success());
+}
+
+#[test]
+fn test_select_count_only_with_filters() {
+    let dir = scratch_dir("count_only");
+    for (title, created) in [
+        ("Old work", "2020-01-02 10:00"),
+        ("New work", "2999-01-02 10:00"),
+        ("Done work", "2999-01-02 10:00"),
+    ] {
+        todo(&dir, &["add", title, "x", created, "work"]);
+    }
+    todo(&dir, &["done", "Done work"]);
+    let count = |args: &[&str]| {
+        let mut all = vec!["select", "--count-only"];
+        all.extend(args);
+        stdout(&todo(&dir, &all))
+    };
+
+    assert_eq!(count(&[r#"category = "work""#]), "3 task(s) matched\n");
+    assert_eq!(
+        count(&[r#"category = "work" and status = "on""#]),
+        "2 task(s) matched\n"
+    );
+    assert_eq!(
+        count(&[r#"status = "on""#, "--older-than", "1d"]),
+        "1 task(s) matched\n"
+    );
+    assert_eq!(count(&[r#"category = "none""#]), "0 task(s) matched\n");
+}
+
+#[test]
+fn test_command_aliases() {
+    let dir = scratch_dir("aliases");
+    for title in ["First", "Second"] {
+        let output = todo(&dir, &["a", title, "Description", "2024-01-02 10:00"]);
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    let listed = stdout(&todo(&dir, &["ls"]));
+    assert!(listed.contains("First: Description (on)"));
+    assert!(listed.contains("Second: Description (on)"));
+
+    assert!(todo(&dir, &["do", "First"]).status.success());
+    assert!(stdout(&todo(&dir, &["ls"])).contains("First: Description (done)"));
+
+    assert!(todo(&dir, &["mv", "First", "other.json"]).status.success());
+    assert!(!stdout(&todo(&dir, &["ls"])).contains("First"));
+    assert!(dir.join("other.json").exists());
+
+    assert!(todo(&dir, &["rm", "Second"]).status.success());
+    assert!(stdout(&todo(&dir, &["ls"])).contains("No tasks found."));
+}
+
+#[test]
+fn test_file_from_stdin_writes_stdout() {
+    let dir = scratch_dir("stdio");
+    assert!(
+        todo(&dir, &["add", "Piped", "Description", "2024-01-02 10:00"])
+            .status
+            .success()
+    );
+    let input = fs::read(dir.join("tasks.json")).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_todo_list"))
+        .args(["--file", "-", "done", "Piped"])
+        .current_dir(dir.as_path())
+        .env("HOME", dir.as_path())
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let written: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(written["tasks"]["Piped"]["status"], "Done");
+    assert_eq!(fs::read(dir.join("tasks.json")).unwrap(), input);
+}
+
+#[test]
+fn test_list_print0_separates_titles_with_nul() {
+    let dir = scratch_dir("print0");
+    for title in ["Two words", "Line\nbreak"] {
+        let output = todo(&dir, &["add", title, "Description", "2024-01-02 10:00"]);
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    let output = todo(&dir, &["list", "--print0"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout.last(), Some(&0));
+    let mut titles: Vec<&[u8]> = output.stdout[..output.stdout.len() - 1]
+        .split(|&byte| byte == 0)
+        .collect();
+    titles.sort();
+    assert_eq!(titles, [&b"Line\nbreak"[..], &b"Two words"[..]]);
+}
+
+#[test]
+fn test_select_fail_on_empty_exit_codes() {
+    let dir = scratch_dir("fail_on_empty");
+    assert!(
+        todo(&dir, &["add", "Task", "Description", "2024-01-02 10:00"])
+            .status
+            .success()
+    );
+
+    let matched = todo(&dir, &["select", r#"status = "on""#, "--fail-on-empty"]);
+    assert_eq!(matched.status.code(), Some(0));
+    let empty = todo(&dir, &["select", r#"status = "done""#, "--fail-on-empty"]);
+    assert_eq!(empty.status.code(), Some(2));
+    let invalid = todo(&dir, &["select", "status ~ on", "--fail-on-empty"]);
+    assert_eq!(invalid.status.code(), Some(1));
+    assert_eq!(
+        todo(&dir, &["select", r#"status = "done""#]).status.code(),
+        Some(0)
+    );
+}
+
+#[test]
+fn test_update_append_description() {
+    let dir = scratch_dir("append_description");
+    assert!(
+        todo(&dir, &["add", "Task", "First line", "2024-01-02 10:00"])
+            .status
+            .success()
+    );
+
+    let output = todo(
+        &dir,
+        &[
+            "update",
+            "Task",
+            "--append-description",
+            "Second line",
+            "--priority",
+            "high",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+
+    let shown = stdout(&todo(&dir, &["show", "Task"]));
+    assert!(shown.contains("Description: First line\nSecond line"));
+    assert!(shown.contains("Priority:    high"));
+}
+
+#[test]
+fn test_done_without_title_fails_when_not_interactive() {
+    let dir = scratch_dir("pick");
+    todo(&dir, &["add", "Only", "Description", "2024-01-02 10:00"]);
+    for command in ["done", "delete"] {
+        let output = todo(&dir, &[command]);
+        assert_eq!(output.status.code(), Some(1), "{:?}", output);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("No title given"));
+    }
+    assert!(stdout(&todo(&dir, &["ls"])).contains("Only: Description (on)"));
+}
+
+#[test]
+fn test_output_writes_results_to_file() {
+    let dir = scratch_dir("output");
+    for title in ["First", "Second"] {
+        todo(&dir, &["add", title, "Description", "2024-01-02 10:00"]);
+    }
+
+    let output = todo(
+        &dir,
+        &[
+            "list",
+            "--format",
+            "{title}",
+            "--output",
+            "reports/list.txt",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert!(stdout(&output).is_empty());
+    let mut titles: Vec<String> = fs::read_to_string(dir.join("reports/list.txt"))
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    titles.sort();
+    assert_eq!(titles, ["First", "Second"]);
+
+    let output = todo(&dir, &["select", r#"title = "First""#, "-o", "first.txt"]);
+    assert!(output.status.success(), "{:?}", output);
+    let selected = fs::read_to_string(dir.join("first.txt")).unwrap();
+    assert!(selected.starts_with("First: Description (on)"));
+    assert_eq!(selected.lines().count(), 1);
+
+    fs::write(dir.join("blocker"), "").unwrap();
+    let output = todo(&dir, &["list", "--output", "blocker/list.txt"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("blocker"));
+}
+
+#[test]
+fn test_interactive_update_asks_before_saving() {
+    let dir = scratch_dir("update_confirm");
+    todo(&dir, &["add", "Task", "Old", "2024-01-02 10:00"]);
+    let update = |args: &[&str], answers: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_todo_list"))
+            .args(args)
+            .current_dir(dir.as_path())
+            .env("HOME", dir.as_path())
+            .env("XDG_CONFIG_HOME", dir.join("config"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(answers.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let output = update(&["update", "Task"], "New\n\n\n\n\nn\n");
+    assert!(output.status.success());
+    let printed = stdout(&output);
+    assert!(printed.contains("Description: New"));
+    assert!(printed.contains("Save changes? [y/N]"));
+    assert!(printed.contains("Aborted."));
+    assert!(stdout(&todo(&dir, &["show", "Task"])).contains("Description: Old"));
+
+    update(&["update", "Task"], "New\n\n\n\n\ny\n");
+    assert!(stdout(&todo(&dir, &["show", "Task"])).contains("Description: New"));
+
+    let output = update(&["update", "Task", "--no-confirm"], "Newer\n\n\n\n\n");
+    assert!(!stdout(&output).contains("Save changes?"));
+    assert!(stdout(&todo(&dir, &["show", "Task"])).contains("Description: Newer"));
+}
+
+#[test]
+fn test_list_created_after_cutoff() {
+    let dir = scratch_dir("created_after");
+    for (title, date) in [
+        ("January", "2024-01-15 10:00"),
+        ("March", "2024-03-15 10:00"),
+    ] {
+        todo(&dir, &["add", title, "Description", date]);
+    }
+
+    let args = ["list", "--json", "--created-after", "2024-02-01 00:00"];
+    let output = todo(&dir, &args);
+    assert!(output.status.success(), "{:?}", output);
+    let exported: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let titles: Vec<&str> = exported
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|task| task["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, ["March"]);
+
+    let output = todo(&dir, &["list", "--json", "--modified-after", "1h"]);
+    let exported: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(exported.as_array().unwrap().len(), 2);
+
+    let output = todo(&dir, &["list", "--created-after", "last week"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_add_from_file_reports_bad_lines() {
+    let dir = scratch_dir("from_file");
+    todo(
+        &dir,
+        &["add", "Existing", "Description", "2024-01-02 10:00"],
+    );
+    fs::write(
+        dir.join("tasks.txt"),
+        "Book movers\nPack kitchen | Plates first | home\n | no title\nExisting\n",
+    )
+    .unwrap();
+
+    let output = todo(
+        &dir,
+        &["add", "--from-file", "tasks.txt", "--priority", "high"],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Line 3: Task title cannot be empty"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("Line 4: Task with title 'Existing' already exists"));
+    assert!(stderr.contains("2 of 4 task(s) failed"));
+    assert!(stdout(&output).contains("Task 'Book movers' added successfully"));
+
+    let shown = stdout(&todo(&dir, &["show", "Pack kitchen"]));
+    assert!(shown.contains("Category:    home"));
+    assert!(shown.contains("Priority:    high"));
+    assert!(todo(&dir, &["show", "Book movers"]).status.success());
+}
+
+#[test]
+fn test_list_separator() {
+    let dir = scratch_dir("separator");
+    todo(
+        &dir,
+        &[
+            "add",
+            "Plan",
+            "Draft: v1 (rough)",
+            "2024-01-02 10:00",
+            "work",
+            "--label",
+            "red",
+        ],
+    );
+
+    let output = todo(&dir, &["list", "--separator", "\\t"]);
+    assert!(output.status.success(), "{:?}", output);
+    let listed = stdout(&output);
+    let columns: Vec<&str> = listed.trim_end_matches('\n').split('\t').collect();
+    assert_eq!(columns.len(), 10);
+    assert_eq!(columns[..4], ["Plan", "Draft: v1 (rough)", "on", "work"]);
+    assert_eq!(columns[5..], ["", "", "red", "", ""]);
+
+    let listed = stdout(&todo(&dir, &["list", "--separator", ";"]));
+    assert_eq!(listed.matches(';').count(), 9);
+}
+
+#[test]
+fn test_add_warns_about_similar_tasks() {
+    let dir = scratch_dir("dup_check");
+    let add = |title: &str, extra: &[&str]| {
+        let mut args = vec![
+            "add",
+            title,
+            "Pick up milk from the corner shop",
+            "2024-01-02 10:00",
+            "home",
+        ];
+        args.extend_from_slice(extra);
+        todo(&dir, &args)
+    };
+    assert!(add("Buy milk", &[]).status.success());
+
+    let output = add("Get milk", &[]);
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Warning: 'Get milk' looks similar to 'Buy milk'"),
+        "{}",
+        stderr
+    );
+
+    let output = add("Fetch milk", &["--no-dup-check"]);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert_eq!(
+        stdout(&todo(&dir, &["list", "--print0"]))
+            .matches('\0')
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn test_list_columns() {
+    let dir = scratch_dir("columns");
+    todo(
+        &dir,
+        &[
+            "add",
+            "Plan",
+            "Draft",
+            "2024-01-02 10:00",
+            "work",
+            "--label",
+            "red",
+        ],
+    );
+
+    let output = todo(&dir, &["list", "--columns", "label,title,status"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(stdout(&output), "red\tPlan\ton\n");
+
+    let output = todo(
+        &dir,
+        &[
+            "list",
+            "--columns",
+            "title,due,category",
+            "--separator",
+            " | ",
+        ],
+    );
+    assert_eq!(stdout(&output), "Plan |  | work\n");
+
+    let output = todo(&dir, &["list", "--columns", "title,owner"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("invalid value 'owner'"), "{}", stderr);
+    assert!(stderr.contains("possible values"), "{}", stderr);
+}
+
+#[test]
+fn test_list_table() {
+    let dir = scratch_dir("table");
+    todo(&dir, &["add", "Plan", "Draft", "2024-01-02 10:00", "work"]);
+
+    let output = todo(&dir, &["list", "--table", "--columns", "title,category"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        stdout(&output),
+        "┌───────┬──────────┐\n\
+         │ Title │ Category │\n\
+         ├───────┼──────────┤\n\
+         │ Plan  │ work     │\n\
+         └───────┴──────────┘\n"
+    );
+
+    let output = todo(&dir, &["list", "--table", "--ascii"]);
+    let listed = stdout(&output);
+    assert!(listed.is_ascii(), "{}", listed);
+    assert!(listed.starts_with("+-------+-------------+"), "{}", listed);
+    assert!(listed.contains("| Plan  | Draft       | on     | work     |"));
+
+    assert!(!todo(&dir, &["list", "--ascii"]).status.success());
+}
+
+#[test]
+fn test_date_format() {
+    let dir = scratch_dir("date_format");
+    let flag = ["--date-format", "%d/%m/%Y %H:%M"];
+    let add = [
+        &flag[..],
+        &["add", "Rent", "Pay", "31/01/2024 09:00", "home"],
+    ]
+    .concat();
+    assert!(todo(&dir, &add).status.success());
+    let listed = stdout(&todo(&dir, &[&flag[..], &["list"]].concat()));
+    assert_eq!(listed, "Rent: Pay (on) - home - 31/01/2024 09:00\n");
+
+    let config_dir = dir.join("config").join("todo");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.toml"),
+        "date_format = \"%d.%m.%Y\"\n",
+    )
+    .unwrap();
+    let output = todo(&dir, &["add", "Taxes", "File", "01.04.2024", "home"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(output.stderr.is_empty());
+    let listed = stdout(&todo(&dir, &["list", "--sort", "created"]));
+    assert!(
+        listed.ends_with("Taxes: File (on) - home - 01.04.2024\n"),
+        "{}",
+        listed
+    );
+
+    fs::write(config_dir.join("config.toml"), "date_format = \"%d/%m\"\n").unwrap();
+    let stderr = String::from_utf8_lossy(&todo(&dir, &["list"]).stderr).into_owned();
+    assert!(stderr.contains("does not round-trip"), "{}", stderr);
+}
+
+#[test]
+fn test_recategorize() {
+    let dir = scratch_dir("recategorize");
+    for title in ["A", "B"] {
+        todo(&dir, &["add", title, "x", "2024-01-02 10:00", "errands"]);
+    }
+
+    let output = todo(&dir, &["recategorize", "errands", "home"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        stdout(&output),
+        "2 task(s) moved from 'errands' to 'home'\n"
+    );
+    assert_eq!(stdout(&todo(&dir, &["categories"])), "home (2)\n");
+
+    let output = todo(&dir, &["move-to-category", "errands", "home"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("No tasks in category 'errands'"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_external_description() {
+    let dir = scratch_dir("external_description");
+    let description = "Retro notes\nWhat went well: everything.\nWhat didn't: nothing.";
+    let output = todo(
+        &dir,
+        &[
+            "add",
+            "Retro",
+            description,
+            "2024-01-02 10:00",
+            "work",
+            "--external-description",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let file = dir.join("tasks.descriptions").join("retro.txt");
+    assert_eq!(fs::read_to_string(&file).unwrap(), description);
+    assert!(!fs::read_to_string(dir.join("tasks.json"))
+        .unwrap()
+        .contains("What went well"));
+
+    let shown = stdout(&todo(&dir, &["show", "Retro"]));
+    assert!(
+        shown.contains(&format!("Description: {}\n", description)),
+        "{}",
+        shown
+    );
+
+    fs::remove_file(&file).unwrap();
+    let output = todo(&dir, &["show", "Retro"]);
+    assert!(output.status.success());
+    let shown = stdout(&output);
+    assert!(
+        shown.contains("Description: Retro notes (full text unavailable: Failed to read"),
+        "{}",
+        shown
+    );
+}
+
+#[test]
+fn test_stats_since() {
+    let dir = scratch_dir("stats_since");
+    let output = todo(&dir, &["stats", "--since", "7d"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("No tasks created or completed since "));
+
+    todo(&dir, &["add", "Old", "x", "2020-01-02 10:00", "work"]);
+    fs::write(dir.join("new.txt"), "New one\nNew two\nNew three\n").unwrap();
+    todo(&dir, &["add", "--from-file", "new.txt"]);
+    todo(&dir, &["done", "Old", "New one"]);
+
+    let output = todo(&dir, &["stats", "--since", "7d"]);
+    let stats = stdout(&output);
+    assert!(stats.contains("Created:         3\n"), "{}", stats);
+    assert!(stats.contains("Completed:       2\n"), "{}", stats);
+    assert!(
+        stats.contains("Completion rate: 33% (1 of 3 created are done)"),
+        "{}",
+        stats
+    );
+    assert!(!todo(&dir, &["stats", "--since", "soon"]).status.success());
+}
+
+#[test]
+fn test_list_html() {
+    let dir = scratch_dir("html");
+    todo(
+        &dir,
+        &["add", "Fix <b>", "a < b", "2024-01-02 10:00", "web"],
+    );
+
+    let output = todo(&dir, &["list", "--html", "-o", "tasks.html"]);
+    assert!(output.status.success(), "{:?}", output);
+    let html = fs::read_to_string(dir.join("tasks.html")).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<summary>web (1)</summary>"));
+    assert!(html.contains("<tr class=\"on\"><td>Fix &lt;b&gt;</td><td>a &lt; b</td>"));
+    assert!(!todo(&dir, &["list", "--html", "--json"]).status.success());
+}
+
+#[test]
+fn test_touch() {
+    let dir = scratch_dir("touch");
+    todo(&dir, &["add", "Stale", "x", "2020-01-02 10:00", "work"]);
+    todo(&dir, &["add", "Newer", "x", "2021-01-02 10:00", "work"]);
+    let titles = || {
+        stdout(&todo(
+            &dir,
+            &["list", "--sort", "created", "--columns", "title"],
+        ))
+    };
+    assert_eq!(titles(), "Stale\nNewer\n");
+
+    let output = todo(&dir, &["touch", "Stale"]);
+    assert_eq!(stdout(&output), "Task 'Stale' touched\n");
+    assert_eq!(titles(), "Stale\nNewer\n");
+
+    todo(&dir, &["touch", "Stale", "--restamp"]);
+    assert_eq!(titles(), "Newer\nStale\n");
+    assert!(!todo(&dir, &["touch", "Missing"]).status.success());
+}
+
+#[test]
+fn test_json_errors() {
+    let dir = scratch_dir("json_errors");
+    let output = todo(&dir, &["delete", "Missing", "--json-errors"]);
+    assert!(!output.status.success());
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(
+        error,
+        serde_json::json!({ "error": "Task with title 'Missing' not found" })
+    );
+
+    fs::write(dir.join("blocker"), "").unwrap();
+    let output = todo(&dir, &["list", "--json", "-o", "blocker/out.json"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("{\"error\":"), "{}", stderr);
+
+    let output = todo(&dir, &["delete", "Missing"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Error: Task with title 'Missing' not found\n"
+    );
+}
+
+#[test]
+fn test_dedupe() {
+    let dir = scratch_dir("dedupe");
+    todo(&dir, &["add", "buy  Milk", "x", "2024-02-01 10:00", "home"]);
+    todo(&dir, &["add", "Buy milk", "x", "2024-01-01 10:00", "home"]);
+    let titles = || {
+        stdout(&todo(
+            &dir,
+            &["list", "--sort", "title", "--columns", "title"],
+        ))
+    };
+
+    let output = todo(&dir, &["dedupe", "--dry-run"]);
+    assert_eq!(
+        stdout(&output),
+        "1 task(s) would be merged:\n  'buy  Milk' into 'Buy milk'\n\
+         Merged 'buy  Milk' into 'Buy milk' (dry run)\n"
+    );
+    let output = todo(&dir, &["dedupe"]);
+    assert!(stdout(&output).ends_with("Aborted.\n"), "{:?}", output);
+    assert_eq!(titles(), "Buy milk\nbuy  Milk\n");
+
+    let output = todo(&dir, &["dedupe", "--yes"]);
+    assert_eq!(stdout(&output), "Merged 'buy  Milk' into 'Buy milk'\n");
+    assert_eq!(titles(), "Buy milk\n");
+    assert_eq!(
+        stdout(&todo(&dir, &["dedupe"])),
+        "No duplicate tasks found.\n"
+    );
+}
+
+#[test]
+fn test_attach() {
+    let dir = scratch_dir("attach");
+    todo(&dir, &["add", "Spec", "x", "2024-01-02 10:00", "work"]);
+    todo(&dir, &["add", "Other", "x", "2024-01-02 10:00", "work"]);
+    fs::write(dir.join("spec.md"), "# Spec").unwrap();
+
+    let output = todo(&dir, &["attach", "Spec", "spec.md"]);
+    assert_eq!(stdout(&output), "Attached 'spec.md' to task 'Spec'\n");
+    assert!(output.stderr.is_empty());
+    let output = todo(&dir, &["attach", "Spec", "missing.pdf"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Warning: 'missing.pdf' does not exist\n"
+    );
+    let output = todo(&dir, &["attach", "Spec", "https://example.com/spec"]);
+    assert!(output.stderr.is_empty());
+
+    let show = stdout(&todo(&dir, &["show", "Spec"]));
+    assert!(
+        show.contains("Attachments:\n  spec.md\n  missing.pdf\n  https://example.com/spec\n"),
+        "{}",
+        show
+    );
+    let output = todo(&dir, &["select", "has attachments", "--count-only"]);
+    assert_eq!(stdout(&output), "1 task(s) matched\n");
+}
+
+#[test]
+fn test_list_status_flags() {
+    let dir = scratch_dir("status_flags");
+    let tasks = [
+        ("Late", "2024-01-01 10:00", "2024-02-01 10:00"),
+        ("Later", "2024-01-02 10:00", "2999-01-01 10:00"),
+        ("Undated", "2024-01-03 10:00", ""),
+        ("Finished", "2024-01-04 10:00", "2024-02-01 10:00"),
+        ("Also late", "2024-01-05 10:00", "2024-03-01 10:00"),
+    ];
+    for (title, created, due) in tasks {
+        let mut args = vec!["add", title, "x", created, "work"];
+        if !due.is_empty() {
+            args.extend(["--due", due]);
+        }
+        todo(&dir, &args);
+    }
+    todo(&dir, &["done", "Finished"]);
+    let titles = |flags: &[&str]| {
+        let mut args = vec!["list", "--columns", "title"];
+        args.extend(flags);
+        stdout(&todo(&dir, &args))
+    };
+
+    assert_eq!(
+        titles(&["--active-only", "--sort", "title"]),
+        "Also late\nLate\nLater\nUndated\n"
+    );
+    assert_eq!(titles(&["--done-only"]), "Finished\n");
+    assert_eq!(
+        titles(&["--overdue-only", "--sort", "title"]),
+        "Also late\nLate\n"
+    );
+    assert_eq!(
+        titles(&["--overdue-only", "--sort", "created"]),
+        "Late\nAlso late\n"
+    );
+    let output = todo(&dir, &["list", "--active-only", "--done-only"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_bulk_preview_and_confirmation() {
+    let dir = scratch_dir("bulk_preview");
+    for (title, category) in [("Write", "work"), ("Review", "work"), ("Shop", "home")] {
+        todo(&dir, &["add", title, "x", "2024-01-02 10:00", category]);
+    }
+    let work = r#"category = "work""#;
+
+    let output = todo(&dir, &["delete", "--where", work]);
+    assert_eq!(
+        stdout(&output),
+        "2 task(s) will be deleted:\n  Review\n  Write\nProceed? [y/N]\nAborted.\n"
+    );
+    let output = todo(&dir, &["done", "--where", work, "--dry-run"]);
+    assert_eq!(
+        stdout(&output),
+        "2 task(s) would be marked as done:\n  Review\n  Write\n\
+         2 task(s) marked as done (dry run)\n"
+    );
+    let output = todo(&dir, &["done", "--where", work]);
+    assert!(stdout(&output).ends_with("Aborted.\n"));
+    assert_eq!(
+        stdout(&todo(
+            &dir,
+            &["select", r#"status = "done""#, "--count-only"]
+        )),
+        "0 task(s) matched\n"
+    );
+
+    let output = todo(&dir, &["done", "--where", work, "--yes"]);
+    assert_eq!(stdout(&output), "2 task(s) marked as done\n");
+    let output = todo(&dir, &["delete", "--where", r#"category = "none""#]);
+    assert_eq!(stdout(&output), "No tasks match the given predicate.\n");
+}
+
+#[test]
+fn test_unreadable_journal_is_an_error() {
+    let dir = scratch_dir("unreadable_journal");
+    fs::create_dir(dir.join("tasks.jsonl")).unwrap();
+    let output = todo(&dir, &["--file", "tasks.jsonl", "list"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.starts_with("Error: Failed to read tasks.jsonl"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}